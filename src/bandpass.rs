@@ -0,0 +1,86 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::biquad::Biquad;
+use crate::config::{BandFilter, BandSlope};
+
+const MAX_SECTIONS: usize = 4;
+
+// Section Qs for a Butterworth high/low-pass of order 2, 4 and 8
+const BUTTERWORTH_Q: [&[f32]; 3] = [
+    &[FRAC_1_SQRT_2],
+    &[0.541_196_1, 1.306_563],
+    &[0.509_795_6, 0.601_344_9, 0.899_976_2, 2.562_915_4],
+];
+
+// Linkwitz-Riley is two Butterworths of half the order in series
+const LINKWITZ_RILEY_Q: [&[f32]; 3] = [
+    &[0.5],
+    &[FRAC_1_SQRT_2, FRAC_1_SQRT_2],
+    &[0.541_196_1, 1.306_563, 0.541_196_1, 1.306_563],
+];
+
+/// Band between two edge frequencies: either the classic resonant band-pass, or
+/// a high-pass at the low edge and a low-pass at the high edge, each built from
+/// cascaded second-order sections for 12/24/48 dB per octave.
+pub struct BandPass {
+    filter: BandFilter,
+    slope: BandSlope,
+    highpass: [Biquad; MAX_SECTIONS],
+    lowpass: [Biquad; MAX_SECTIONS],
+}
+
+impl BandPass {
+    pub fn new(filter: BandFilter, slope: BandSlope) -> Self {
+        Self {
+            filter,
+            slope,
+            highpass: Default::default(),
+            lowpass: Default::default(),
+        }
+    }
+
+    /// Switch filter type or order. Returns true if it changed, in which case
+    /// the filter state was cleared and `update` must be called again.
+    pub fn set_shape(&mut self, filter: BandFilter, slope: BandSlope) -> bool {
+        if filter == self.filter && slope == self.slope {
+            return false;
+        }
+        *self = Self::new(filter, slope);
+        true
+    }
+
+    fn section_qs(&self) -> &'static [f32] {
+        let order = self.slope.index() as usize;
+        match self.filter {
+            BandFilter::Resonant => &[],
+            BandFilter::Butterworth => BUTTERWORTH_Q[order],
+            BandFilter::LinkwitzRiley => LINKWITZ_RILEY_Q[order],
+        }
+    }
+
+    pub fn update(&mut self, sample_rate: f32, low_hz: f32, high_hz: f32) {
+        if self.filter == BandFilter::Resonant {
+            self.highpass[0].update_bandpass(sample_rate, low_hz, high_hz);
+            return;
+        }
+        for (i, &q) in self.section_qs().iter().enumerate() {
+            self.highpass[i].update_highpass(sample_rate, low_hz, q);
+            self.lowpass[i].update_lowpass(sample_rate, high_hz, q);
+        }
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        if self.filter == BandFilter::Resonant {
+            return self.highpass[0].process(input);
+        }
+        let n = self.section_qs().len();
+        let mut out = input;
+        for section in &mut self.highpass[..n] {
+            out = section.process(out);
+        }
+        for section in &mut self.lowpass[..n] {
+            out = section.process(out);
+        }
+        out
+    }
+}
@@ -1,3 +1,4 @@
+/// Direct form I biquad with the RBJ "Audio EQ Cookbook" designs
 pub struct Biquad {
     pub(crate) a0: f32,
     pub(crate) a1: f32,
@@ -11,6 +12,33 @@ pub struct Biquad {
     y2: f32,
 }
 
+// Design frequencies are held below this fraction of the rate; at Nyquist and above
+// the cookbook formulas turn unstable, and fixed-Hz designs must work at any output rate
+const MAX_FREQ_RATIO: f32 = 0.45;
+
+fn omega(sample_rate: f32, freq: f32) -> f32 {
+    2.0 * std::f32::consts::PI * freq.min(MAX_FREQ_RATIO * sample_rate) / sample_rate
+}
+
+// cos(w0) and alpha = sin(w0) / 2Q
+fn prewarp(sample_rate: f32, freq: f32, q: f32) -> (f32, f32) {
+    let omega = omega(sample_rate, freq);
+    (omega.cos(), omega.sin() / (2.0 * q))
+}
+
+// cos(w0) and 2·sqrt(A)·alpha for shelves with slope S
+fn shelf_prewarp(sample_rate: f32, freq: f32, a: f32, slope: f32) -> (f32, f32) {
+    let omega = omega(sample_rate, freq);
+    let alpha = omega.sin() / 2.0 * ((a + 1.0 / a) * (1.0 / slope - 1.0) + 2.0).max(0.0).sqrt();
+    (omega.cos(), 2.0 * a.sqrt() * alpha)
+}
+
+impl Default for Biquad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Biquad {
     pub fn new() -> Self {
         Self {
@@ -27,12 +55,49 @@ impl Biquad {
         }
     }
 
+    /// Constant 0 dB peak band-pass spanning `low_hz`..`high_hz`
     pub fn update_bandpass(&mut self, sample_rate: f32, low_hz: f32, high_hz: f32) {
         let center = (low_hz * high_hz).sqrt();
-        let omega = 2.0 * std::f32::consts::PI * center / sample_rate;
-        let alpha = omega.sin() / (2.0 * (center / (high_hz - low_hz)));
-        let cos_w = omega.cos();
+        self.update_bandpass_cpg(sample_rate, center, center / (high_hz - low_hz));
+    }
 
+    pub fn update_lowpass(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w, alpha) = prewarp(sample_rate, freq, q);
+        self.b0 = (1.0 - cos_w) / 2.0;
+        self.b1 = 1.0 - cos_w;
+        self.b2 = (1.0 - cos_w) / 2.0;
+        self.a0 = 1.0 + alpha;
+        self.a1 = -2.0 * cos_w;
+        self.a2 = 1.0 - alpha;
+        self.normalize();
+    }
+
+    pub fn update_highpass(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w, alpha) = prewarp(sample_rate, freq, q);
+        self.b0 = (1.0 + cos_w) / 2.0;
+        self.b1 = -(1.0 + cos_w);
+        self.b2 = (1.0 + cos_w) / 2.0;
+        self.a0 = 1.0 + alpha;
+        self.a1 = -2.0 * cos_w;
+        self.a2 = 1.0 - alpha;
+        self.normalize();
+    }
+
+    /// Band-pass with constant skirt gain (peak gain = Q)
+    pub fn update_bandpass_csg(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w, alpha) = prewarp(sample_rate, freq, q);
+        self.b0 = q * alpha;
+        self.b1 = 0.0;
+        self.b2 = -q * alpha;
+        self.a0 = 1.0 + alpha;
+        self.a1 = -2.0 * cos_w;
+        self.a2 = 1.0 - alpha;
+        self.normalize();
+    }
+
+    /// Band-pass with constant 0 dB peak gain
+    pub fn update_bandpass_cpg(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w, alpha) = prewarp(sample_rate, freq, q);
         self.b0 = alpha;
         self.b1 = 0.0;
         self.b2 = -alpha;
@@ -42,6 +107,66 @@ impl Biquad {
         self.normalize();
     }
 
+    pub fn update_notch(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w, alpha) = prewarp(sample_rate, freq, q);
+        self.b0 = 1.0;
+        self.b1 = -2.0 * cos_w;
+        self.b2 = 1.0;
+        self.a0 = 1.0 + alpha;
+        self.a1 = -2.0 * cos_w;
+        self.a2 = 1.0 - alpha;
+        self.normalize();
+    }
+
+    pub fn update_allpass(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w, alpha) = prewarp(sample_rate, freq, q);
+        self.b0 = 1.0 - alpha;
+        self.b1 = -2.0 * cos_w;
+        self.b2 = 1.0 + alpha;
+        self.a0 = 1.0 + alpha;
+        self.a1 = -2.0 * cos_w;
+        self.a2 = 1.0 - alpha;
+        self.normalize();
+    }
+
+    pub fn update_peaking(&mut self, sample_rate: f32, freq: f32, q: f32, gain_db: f32) {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w, alpha) = prewarp(sample_rate, freq, q);
+        self.b0 = 1.0 + alpha * a;
+        self.b1 = -2.0 * cos_w;
+        self.b2 = 1.0 - alpha * a;
+        self.a0 = 1.0 + alpha / a;
+        self.a1 = -2.0 * cos_w;
+        self.a2 = 1.0 - alpha / a;
+        self.normalize();
+    }
+
+    /// Low shelf; `slope` = 1.0 is the steepest without overshoot
+    pub fn update_lowshelf(&mut self, sample_rate: f32, freq: f32, gain_db: f32, slope: f32) {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w, beta) = shelf_prewarp(sample_rate, freq, a, slope);
+        self.b0 = a * ((a + 1.0) - (a - 1.0) * cos_w + beta);
+        self.b1 = 2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w);
+        self.b2 = a * ((a + 1.0) - (a - 1.0) * cos_w - beta);
+        self.a0 = (a + 1.0) + (a - 1.0) * cos_w + beta;
+        self.a1 = -2.0 * ((a - 1.0) + (a + 1.0) * cos_w);
+        self.a2 = (a + 1.0) + (a - 1.0) * cos_w - beta;
+        self.normalize();
+    }
+
+    /// High shelf; `slope` = 1.0 is the steepest without overshoot
+    pub fn update_highshelf(&mut self, sample_rate: f32, freq: f32, gain_db: f32, slope: f32) {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w, beta) = shelf_prewarp(sample_rate, freq, a, slope);
+        self.b0 = a * ((a + 1.0) + (a - 1.0) * cos_w + beta);
+        self.b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w);
+        self.b2 = a * ((a + 1.0) + (a - 1.0) * cos_w - beta);
+        self.a0 = (a + 1.0) - (a - 1.0) * cos_w + beta;
+        self.a1 = 2.0 * ((a - 1.0) - (a + 1.0) * cos_w);
+        self.a2 = (a + 1.0) - (a - 1.0) * cos_w - beta;
+        self.normalize();
+    }
+
     fn normalize(&mut self) {
         let inv = 1.0 / self.a0;
         self.b0 *= inv;
@@ -63,3 +188,92 @@ impl Biquad {
         out
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: f32 = 48000.0;
+
+    /// |H(e^jw)| in dB at `freq`, evaluated from the coefficients
+    fn magnitude_db(f: &Biquad, freq: f32) -> f64 {
+        let w = 2.0 * std::f64::consts::PI * freq as f64 / SR as f64;
+        let (b0, b1, b2) = (f.b0 as f64, f.b1 as f64, f.b2 as f64);
+        let (a1, a2) = (f.a1 as f64, f.a2 as f64);
+        let (c1, s1, c2, s2) = (w.cos(), w.sin(), (2.0 * w).cos(), (2.0 * w).sin());
+        let num = (b0 + b1 * c1 + b2 * c2).powi(2) + (b1 * s1 + b2 * s2).powi(2);
+        let den = (1.0 + a1 * c1 + a2 * c2).powi(2) + (a1 * s1 + a2 * s2).powi(2);
+        10.0 * (num / den).log10()
+    }
+
+    fn assert_db(f: &Biquad, freq: f32, expected: f64) {
+        let got = magnitude_db(f, freq);
+        assert!(
+            (got - expected).abs() < 0.05,
+            "{got:.3} dB at {freq} Hz, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn lowpass_and_highpass() {
+        let mut f = Biquad::new();
+        f.update_lowpass(SR, 1000.0, std::f32::consts::FRAC_1_SQRT_2);
+        assert_db(&f, 1.0, 0.0);
+        assert_db(&f, 1000.0, -3.01);
+        assert!(magnitude_db(&f, 10000.0) < -35.0);
+
+        f.update_highpass(SR, 1000.0, std::f32::consts::FRAC_1_SQRT_2);
+        assert_db(&f, 20000.0, 0.0);
+        assert_db(&f, 1000.0, -3.01);
+        assert!(magnitude_db(&f, 100.0) < -35.0);
+    }
+
+    #[test]
+    fn band_passes() {
+        let mut f = Biquad::new();
+        f.update_bandpass_cpg(SR, 1000.0, 2.0);
+        assert_db(&f, 1000.0, 0.0);
+
+        f.update_bandpass_csg(SR, 1000.0, 2.0);
+        assert_db(&f, 1000.0, 6.02);
+
+        // Edges land on the -3 dB points
+        f.update_bandpass(SR, 500.0, 2000.0);
+        assert_db(&f, 1000.0, 0.0);
+        assert_db(&f, 500.0, -3.01);
+        assert_db(&f, 2000.0, -3.01);
+    }
+
+    #[test]
+    fn notch_and_allpass() {
+        let mut f = Biquad::new();
+        f.update_notch(SR, 1000.0, 2.0);
+        assert!(magnitude_db(&f, 1000.0) < -60.0);
+        assert_db(&f, 1.0, 0.0);
+        assert_db(&f, 20000.0, 0.0);
+
+        f.update_allpass(SR, 1000.0, 2.0);
+        for freq in [20.0, 500.0, 1000.0, 2000.0, 20000.0] {
+            assert_db(&f, freq, 0.0);
+        }
+    }
+
+    #[test]
+    fn peaking_and_shelves() {
+        let mut f = Biquad::new();
+        f.update_peaking(SR, 1000.0, 1.0, 6.0);
+        assert_db(&f, 1000.0, 6.0);
+        assert_db(&f, 1.0, 0.0);
+
+        // Shelves pass half their gain at the corner
+        f.update_lowshelf(SR, 200.0, 6.0, 1.0);
+        assert_db(&f, 1.0, 6.0);
+        assert_db(&f, 200.0, 3.0);
+        assert_db(&f, 20000.0, 0.0);
+
+        f.update_highshelf(SR, 4000.0, 6.0, 1.0);
+        assert_db(&f, 1.0, 0.0);
+        assert_db(&f, 4000.0, 3.0);
+        assert_db(&f, SR / 2.0, 6.0);
+    }
+}
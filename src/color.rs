@@ -0,0 +1,103 @@
+use crate::biquad::Biquad;
+use crate::config::{DEFAULT_SAMPLE_RATE, NoiseColor};
+
+// Output trims so every color lands near the RMS of brown at the default
+// alpha (~0.03); keeps switching colors from jumping in loudness.
+const WHITE_GAIN: f32 = 0.05;
+const PINK_GAIN: f32 = 0.017;
+const BLUE_GAIN: f32 = 0.028;
+const VIOLET_GAIN: f32 = 0.036;
+const GREY_GAIN: f32 = 0.022;
+
+/// Paul Kellet's refined pink filter (-3 dB/oct, ±0.05 dB above 9 Hz at 44.1 kHz).
+/// The coefficients aren't rescaled for the output rate: every corner moves with the
+/// rate, and since a 1/f spectrum looks the same when stretched, the level per Hz stays
+/// put. Only the bottom of the accurate range moves, to ~20 Hz at 96 kHz.
+struct PinkFilter {
+    b: [f32; 7],
+}
+
+impl PinkFilter {
+    fn new() -> Self {
+        Self { b: [0.0; 7] }
+    }
+
+    fn process(&mut self, white: f32) -> f32 {
+        let b = &mut self.b;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.969 * b[2] + white * 0.153852;
+        b[3] = 0.8665 * b[3] + white * 0.3104856;
+        b[4] = 0.55 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.016898;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        pink
+    }
+}
+
+/// Turns one channel of white noise into the selected noise color
+pub struct ColorSource {
+    pink: PinkFilter,
+    brown: f32,
+    // Raw white noise spreads the same power over a wider band at higher rates, so its
+    // level per Hz falls with sqrt(rate); these undo that for the colors built on it.
+    // Pink needs none (see PinkFilter); each first difference adds another factor of rate.
+    white_trim: f32,
+    blue_trim: f32,
+    violet_trim: f32,
+    last_white: f32,
+    last_pink: f32,
+    // Inverse ISO 226 (60 phon) approximation: sub-bass lift, 3.5 kHz dip, air shelf
+    grey: [Biquad; 3],
+}
+
+impl ColorSource {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut grey = [Biquad::new(), Biquad::new(), Biquad::new()];
+        grey[0].update_peaking(sample_rate, 35.0, 0.45, 26.0);
+        grey[1].update_peaking(sample_rate, 3500.0, 1.0, -6.5);
+        grey[2].update_highshelf(sample_rate, 8000.0, 9.0, 1.0);
+
+        let rate = sample_rate / DEFAULT_SAMPLE_RATE as f32;
+        Self {
+            pink: PinkFilter::new(),
+            brown: 0.0,
+            white_trim: rate.sqrt(),
+            blue_trim: rate,
+            violet_trim: rate * rate.sqrt(),
+            last_white: 0.0,
+            last_pink: 0.0,
+            grey,
+        }
+    }
+
+    pub fn process(&mut self, color: NoiseColor, white: f32, alpha: f32) -> f32 {
+        // The integrator always runs so Brown stays continuous when switched back to
+        self.brown = (1.0 - alpha) * white + alpha * self.brown;
+
+        let out = match color {
+            NoiseColor::White => white * WHITE_GAIN * self.white_trim,
+            NoiseColor::Pink => self.pink.process(white) * PINK_GAIN,
+            NoiseColor::Brown => self.brown * self.white_trim,
+            NoiseColor::Blue => {
+                // First difference of pink: -3 dB/oct + 6 dB/oct = +3 dB/oct
+                let pink = self.pink.process(white);
+                let blue = pink - self.last_pink;
+                self.last_pink = pink;
+                blue * BLUE_GAIN * self.blue_trim
+            }
+            NoiseColor::Violet => (white - self.last_white) * VIOLET_GAIN * self.violet_trim,
+            NoiseColor::Grey => {
+                let mut g = white;
+                for f in self.grey.iter_mut() {
+                    g = f.process(g);
+                }
+                g * GREY_GAIN * self.white_trim
+            }
+        };
+
+        self.last_white = white;
+        out
+    }
+}
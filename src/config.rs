@@ -1,28 +1,461 @@
 use anyhow::Result;
+use notify::{EventKind, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+use crate::sample::SampleLoader;
+use crate::triple_buffer::{Reader, Writer, triple_buffer};
+
+/// Adds `ALL` in the order of the matching Slint picker, plus `index` and
+/// `from_index` to convert to and from the picker's selection. Unknown
+/// indices fall back to the enum's default.
+macro_rules! ui_choices {
+    ($ty:ident, $slint:literal, [$($variant:ident),+ $(,)?]) => {
+        impl $ty {
+            #[doc = concat!("UI order, matches the picker in `", $slint, "`")]
+            pub const ALL: &'static [$ty] = &[$($ty::$variant),+];
+
+            pub fn index(self) -> i32 {
+                Self::ALL.iter().position(|v| *v == self).unwrap_or(0) as i32
+            }
+
+            pub fn from_index(i: i32) -> Self {
+                Self::ALL
+                    .get(i.max(0) as usize)
+                    .copied()
+                    .unwrap_or_default()
+            }
+        }
+    };
+}
+
+/// Spectral color of the base noise source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseColor {
+    /// Flat spectrum (0 dB/oct)
+    White,
+    /// Equal energy per octave (-3 dB/oct)
+    Pink,
+    /// Leaky-integrated white, -6 dB/oct above `brown_cutoff_hz`
+    #[default]
+    Brown,
+    /// Differentiated pink (+3 dB/oct)
+    Blue,
+    /// Differentiated white (+6 dB/oct)
+    Violet,
+    /// White weighted by an inverse ISO 226 equal-loudness contour
+    Grey,
+}
+
+ui_choices!(
+    NoiseColor,
+    "noisecolor.slint",
+    [White, Pink, Brown, Blue, Violet, Grey]
+);
+
+/// Shape of the sleep timer fade-out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FadeCurve {
+    /// Gain falls in a straight line
+    Linear,
+    /// Equal dB per minute (sounds even to the ear)
+    #[default]
+    Exponential,
+    /// Slow start and end, faster in the middle
+    SCurve,
+}
+
+ui_choices!(FadeCurve, "sleeptimer.slint", [Linear, Exponential, SCurve]);
+
+impl FadeCurve {
+    /// Gain for `x` = fraction of the fade still remaining (1.0 = fade start, 0.0 = silent)
+    pub fn gain(self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => x,
+            // 0 dB to -60 dB, offset so the end lands exactly on silence
+            FadeCurve::Exponential => (10.0_f32.powf(-3.0 * (1.0 - x)) - 0.001) / 0.999,
+            FadeCurve::SCurve => 0.5 - 0.5 * (std::f32::consts::PI * x).cos(),
+        }
+    }
+}
+
+/// How a band is cut out of the noise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BandFilter {
+    /// Single resonant band-pass centred between the edges (gentle 6 dB/oct skirts)
+    #[default]
+    Resonant,
+    /// High-pass at the low edge, low-pass at the high edge; flat passband
+    Butterworth,
+    /// Like Butterworth but -6 dB at the edges, so adjacent bands sum flat
+    LinkwitzRiley,
+}
+
+ui_choices!(
+    BandFilter,
+    "bands.slint",
+    [Resonant, Butterworth, LinkwitzRiley]
+);
+
+/// Edge steepness for the Butterworth and Linkwitz-Riley band filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum BandSlope {
+    #[serde(rename = "12")]
+    Db12,
+    #[default]
+    #[serde(rename = "24")]
+    Db24,
+    #[serde(rename = "48")]
+    Db48,
+}
+
+ui_choices!(BandSlope, "bands.slint", [Db12, Db24, Db48]);
+
+/// What the bass boost shelf is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoostTarget {
+    /// Only the band reaching lowest
+    #[default]
+    Low,
+    /// The sum of all bands
+    Mix,
+}
+
+ui_choices!(BoostTarget, "bassboost.slint", [Low, Mix]);
+
+/// How the tone layer produces its beat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToneMode {
+    /// A slightly different pitch in each ear (needs headphones)
+    #[default]
+    Binaural,
+    /// One pitch switched on and off at the pulse rate
+    Isochronic,
+    /// Both pitches mixed into each ear, so the beat is acoustic
+    Monaural,
+}
+
+ui_choices!(ToneMode, "tone.slint", [Binaural, Isochronic, Monaural]);
+
+/// Shape of each isochronic pulse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToneEnvelope {
+    /// Flat top with short edges, the classic hard gate
+    Square,
+    /// Straight rise and fall
+    Triangle,
+    /// Raised cosine, the softest pulse
+    #[default]
+    Sine,
+}
+
+ui_choices!(ToneEnvelope, "tone.slint", [Square, Triangle, Sine]);
+
+impl ToneEnvelope {
+    /// Gain at `x` = position within the pulse (0.0–1.0); `edge` is the
+    /// square pulse's rise/fall time as a fraction of the pulse
+    pub fn gain(self, x: f32, edge: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            ToneEnvelope::Square => (x.min(1.0 - x) / edge.max(1e-6)).min(1.0),
+            ToneEnvelope::Triangle => 1.0 - (2.0 * x - 1.0).abs(),
+            ToneEnvelope::Sine => 0.5 - 0.5 * (std::f32::consts::TAU * x).cos(),
+        }
+    }
+}
+
+/// Upper limit on `Config::bands`; the audio thread keeps this many filter slots
+pub const MAX_BANDS: usize = 8;
+
+/// One frequency band cut out of the noise
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Band {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Edges in Hz (1–20000)
+    pub range: [f32; 2],
+    /// Gain multiplier (0.0–10.0)
+    pub gain: f32,
+    /// Stereo position (-1.0 = left, 0.0 = center, 1.0 = right)
+    #[serde(default)]
+    pub pan: f32,
+    #[serde(default)]
+    pub filter: BandFilter,
+    #[serde(default)]
+    pub slope: BandSlope,
+}
+
+impl Band {
+    pub fn new(name: &str, range: [f32; 2], gain: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: true,
+            range,
+            gain,
+            pan: 0.0,
+            filter: BandFilter::default(),
+            slope: BandSlope::default(),
+        }
+    }
+}
+
+/// Synthesized ambience layered over the noise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Soundscape {
+    #[default]
+    Off,
+    /// Bright hiss with scattered droplets
+    Rain,
+    /// Gusting noise through a slowly sweeping resonance
+    Wind,
+    /// Waves that build, break and wash out
+    Surf,
+}
+
+ui_choices!(Soundscape, "soundscape.slint", [Off, Rain, Wind, Surf]);
+
+/// Waveform of an LFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    /// A new random level each cycle, eased into with a cosine
+    Random,
+}
+
+ui_choices!(LfoShape, "lfo.slint", [Sine, Triangle, Random]);
+
+/// What an LFO moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LfoTarget {
+    /// Master gain
+    #[default]
+    Volume,
+    /// Gain of band `Lfo::band`
+    Gain,
+    /// Both edges of band `Lfo::band`, shifted together
+    Center,
+}
+
+ui_choices!(LfoTarget, "lfo.slint", [Volume, Gain, Center]);
+
+/// Upper limit on `Config::lfos`; the audio thread keeps this many oscillators
+pub const MAX_LFOS: usize = 4;
+
+/// Slow modulation source, e.g. for a surf-like swell
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Lfo {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub shape: LfoShape,
+    #[serde(default)]
+    pub target: LfoTarget,
+    /// Index into `Config::bands` for the band targets
+    #[serde(default)]
+    pub band: usize,
+    /// Cycles per second (0.01–5.0)
+    pub rate: f32,
+    /// Modulation amount (0.0–1.0): gain dips down to `1 - depth`, band centers
+    /// swing up to `2 * depth` octaves either way
+    pub depth: f32,
+}
+
+impl Default for Lfo {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shape: LfoShape::Sine,
+            target: LfoTarget::Volume,
+            band: 0,
+            rate: 0.1,
+            depth: 0.5,
+        }
+    }
+}
+
+/// Upper limit on `Config::layers`; the audio thread keeps this many players
+pub const MAX_LAYERS: usize = 4;
+
+/// A recorded WAV or FLAC loop mixed with the noise
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SampleLayer {
+    /// Local file path
+    pub path: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Gain multiplier (0.0–2.0)
+    pub gain: f32,
+}
+
+impl Default for SampleLayer {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            enabled: true,
+            gain: 0.5,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// The old fixed layout: key suffix, name, default range, default volume, and the
+// share of the mix it got (mid and high were summed at half level)
+const LEGACY_BANDS: [(&str, &str, [f32; 2], f32, f32); 3] = [
+    ("low", "Low", [10.0, 500.0], 1.0, 1.0),
+    ("mid", "Mid", [60.0, 1000.0], 0.5, 0.5),
+    ("high", "High", [100.0, 10000.0], 0.2, 0.5),
+];
+
+// Upper limits the fixed low/mid/high ranges were clamped to
+const LEGACY_MAX_HZ: [f32; 3] = [1000.0, 5000.0, 10000.0];
+
+/// Clamp and order low/mid/high ranges the way the fixed bands were:
+/// each band starts no lower than where the one below it ends
+fn order_legacy_ranges(ranges: &mut [[f32; 2]; 3]) {
+    for (range, max) in ranges.iter_mut().zip(LEGACY_MAX_HZ) {
+        range[0] = range[0].clamp(1.0, max);
+        range[1] = range[1].clamp(1.0, max);
+        if range[0] > range[1] {
+            range.swap(0, 1);
+        }
+    }
+    for i in 1..ranges.len() {
+        let below = ranges[i - 1][1];
+        let range = &mut ranges[i];
+        if below > range[0] {
+            range[0] = below.clamp(1.0, LEGACY_MAX_HZ[i]);
+            if range[0] > range[1] {
+                range[1] = range[0];
+            }
+        }
+    }
+}
+
+fn default_bands() -> Vec<Band> {
+    let mut ranges = LEGACY_BANDS.map(|(_, _, range, ..)| range);
+    order_legacy_ranges(&mut ranges);
+    LEGACY_BANDS
+        .iter()
+        .zip(ranges)
+        .map(|((_, name, _, volume, mix), range)| Band::new(name, range, volume * mix))
+        .collect()
+}
+
+fn default_stereo_width() -> f32 {
+    1.0
+}
+
+fn default_bass_boost_freq() -> f32 {
+    100.0
+}
+
+fn default_bass_boost_slope() -> f32 {
+    1.0
+}
+
+/// Rate used when `Config::sample_rate` is 0. The audio backend can't report the
+/// device's own rate, and 48 kHz is what nearly every system mixer runs at.
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+/// Range accepted for `Config::sample_rate` and `render --rate`
+pub const MIN_SAMPLE_RATE: u32 = 8000;
+pub const MAX_SAMPLE_RATE: u32 = 192000;
+
+fn default_brown_cutoff_hz() -> f32 {
+    30.0
+}
+
+fn default_buffer_frames() -> u32 {
+    1024
+}
+
+fn default_fade_ms() -> f32 {
+    150.0
+}
+
+fn default_soundscape_level() -> f32 {
+    0.5
+}
+
+fn default_tone_carrier() -> f32 {
+    200.0
+}
+
+fn default_tone_beat() -> f32 {
+    10.0
+}
+
+fn default_tone_duty() -> f32 {
+    0.5
+}
+
+fn default_tone_level() -> f32 {
+    0.1
+}
+
+fn default_sleep_minutes() -> f32 {
+    60.0
+}
+
+fn default_sleep_fade_minutes() -> f32 {
+    10.0
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Config {
     /// Master volume (0.0–1.0)
     pub volume: f32,
 
-    /// Band toggles
-    pub enable_low: bool,
-    pub enable_mid: bool,
-    pub enable_high: bool,
+    /// Gain ramp used for play, pause and quit, in milliseconds (0–2000)
+    #[serde(default = "default_fade_ms")]
+    pub fade_ms: f32,
 
-    /// Smoothing factor for noise blending (0.9–0.9999)
-    pub alpha: f32,
+    /// Output sample rate in Hz (8000–192000), or 0 for `DEFAULT_SAMPLE_RATE`.
+    /// Read when the audio stream opens, so changes apply on the next start.
+    #[serde(default)]
+    pub sample_rate: u32,
+
+    /// Frames per audio callback (64–8192); smaller is lower latency but
+    /// needs a faster CPU. Like `sample_rate`, applies on the next start.
+    #[serde(default = "default_buffer_frames")]
+    pub buffer_frames: u32,
 
-    /// Frequency bands in Hz (low, mid, high)
-    pub band_low: [f32; 2],
-    pub band_mid: [f32; 2],
-    pub band_high: [f32; 2],
+    /// Base noise color fed into the bands
+    #[serde(default)]
+    pub noise_color: NoiseColor,
 
-    /// Per-band gain multipliers (0.0–10.0)
-    pub band_volume_low: f32,
-    pub band_volume_mid: f32,
-    pub band_volume_high: f32,
+    /// Corner of the `Brown` integrator in Hz (0.5–800); lower is smoother, less "spitty"
+    #[serde(default = "default_brown_cutoff_hz")]
+    pub brown_cutoff_hz: f32,
+
+    /// Spectral tilt applied on top of the color (-6.0–6.0 dB/octave, pivot 1 kHz)
+    #[serde(default)]
+    pub tilt_db_per_octave: f32,
+
+    /// Stereo width (0.0 = mono, 1.0 = independent channels, up to 2.0 = widened)
+    #[serde(default = "default_stereo_width")]
+    pub stereo_width: f32,
+
+    /// Master left/right balance (-1.0 = left only, 0.0 = center, 1.0 = right only)
+    #[serde(default)]
+    pub balance: f32,
 
     /// Noise seed (0–65535)
     pub seed: u64,
@@ -33,6 +466,83 @@ pub struct Config {
     /// Bass enhancement strength (0.0–10.0)
     #[serde(default)]
     pub bass_boost: f32,
+
+    /// Bass boost shelf corner in Hz (20–500)
+    #[serde(default = "default_bass_boost_freq")]
+    pub bass_boost_freq: f32,
+
+    /// Bass boost shelf slope (0.1–1.0, 1.0 = steepest without overshoot)
+    #[serde(default = "default_bass_boost_slope")]
+    pub bass_boost_slope: f32,
+
+    /// Whether the boost shapes the lowest band or the whole mix
+    #[serde(default)]
+    pub bass_boost_target: BoostTarget,
+
+    /// Sleep timer: fade out and pause after `sleep_minutes` of playback
+    #[serde(default)]
+    pub sleep_timer: bool,
+
+    /// Sleep timer length in minutes (15–480)
+    #[serde(default = "default_sleep_minutes")]
+    pub sleep_minutes: f32,
+
+    /// Fade-out length at the end of the timer, in minutes (0–60)
+    #[serde(default = "default_sleep_fade_minutes")]
+    pub sleep_fade_minutes: f32,
+
+    /// Shape of the fade-out
+    #[serde(default)]
+    pub sleep_fade_curve: FadeCurve,
+
+    /// Rain, wind or surf generator mixed with the noise
+    #[serde(default)]
+    pub soundscape: Soundscape,
+
+    /// Soundscape level (0.0–1.0)
+    #[serde(default = "default_soundscape_level")]
+    pub soundscape_level: f32,
+
+    /// Binaural tone layer mixed under the noise
+    #[serde(default)]
+    pub tone: bool,
+
+    /// Tone pitch in Hz, centered between the two ears (40–1000)
+    #[serde(default = "default_tone_carrier")]
+    pub tone_carrier: f32,
+
+    /// Binaural, isochronic or monaural
+    #[serde(default)]
+    pub tone_mode: ToneMode,
+
+    /// Beat or pulse rate in Hz (0.5–40): the pitch difference for binaural and
+    /// monaural, pulses per second for isochronic
+    #[serde(default = "default_tone_beat")]
+    pub tone_beat: f32,
+
+    /// Isochronic: fraction of each cycle the tone is on (0.1–0.9)
+    #[serde(default = "default_tone_duty")]
+    pub tone_duty: f32,
+
+    /// Isochronic: pulse shape
+    #[serde(default)]
+    pub tone_envelope: ToneEnvelope,
+
+    /// Tone level (0.0–1.0)
+    #[serde(default = "default_tone_level")]
+    pub tone_level: f32,
+
+    /// Frequency bands mixed into the output, at most [`MAX_BANDS`]
+    #[serde(default = "default_bands")]
+    pub bands: Vec<Band>,
+
+    /// Low-frequency oscillators modulating volume or bands, at most [`MAX_LFOS`]
+    #[serde(default)]
+    pub lfos: Vec<Lfo>,
+
+    /// Sample loops mixed with the noise, at most [`MAX_LAYERS`]
+    #[serde(default)]
+    pub layers: Vec<SampleLayer>,
 }
 
 // Default config generated on first run
@@ -40,74 +550,363 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             volume: 1.0,
-            alpha: 0.996,
-
-            enable_low: true,
-            enable_mid: true,
-            enable_high: true,
-
-            band_low: [10.0, 500.0],
-            band_mid: [60.0, 1000.0],
-            band_high: [100.0, 10000.0],
-
-            band_volume_low: 1.0,
-            band_volume_mid: 0.5,
-            band_volume_high: 0.2,
+            fade_ms: default_fade_ms(),
+            sample_rate: 0,
+            buffer_frames: default_buffer_frames(),
+            noise_color: NoiseColor::Brown,
+            brown_cutoff_hz: default_brown_cutoff_hz(),
+            tilt_db_per_octave: 0.0,
+            stereo_width: default_stereo_width(),
+            balance: 0.0,
 
             seed: 0,
             random: true,
             bass_boost: 0.0,
+            bass_boost_freq: default_bass_boost_freq(),
+            bass_boost_slope: default_bass_boost_slope(),
+            bass_boost_target: BoostTarget::Low,
+
+            sleep_timer: false,
+            sleep_minutes: default_sleep_minutes(),
+            sleep_fade_minutes: default_sleep_fade_minutes(),
+            sleep_fade_curve: FadeCurve::Exponential,
+
+            soundscape: Soundscape::Off,
+            soundscape_level: default_soundscape_level(),
+
+            tone: false,
+            tone_carrier: default_tone_carrier(),
+            tone_mode: ToneMode::Binaural,
+            tone_beat: default_tone_beat(),
+            tone_duty: default_tone_duty(),
+            tone_envelope: ToneEnvelope::Sine,
+            tone_level: default_tone_level(),
+
+            bands: default_bands(),
+            lfos: Vec::new(),
+            layers: Vec::new(),
         }
     }
 }
 
+/// Read-only factory presets, in the order they are listed in the UI
+pub const BUILTIN_PRESETS: [&str; 7] = [
+    "White",
+    "Pink",
+    "Brown",
+    "Deep Brown",
+    "Rain",
+    "Fan",
+    "Baby Shush",
+];
+
 impl Config {
-    /// Clamp all fields to sane operating ranges and enforce band ordering
+    /// Rate to open the audio stream at, resolving 0 to `DEFAULT_SAMPLE_RATE`
+    pub fn output_sample_rate(&self) -> u32 {
+        match self.sample_rate {
+            0 => DEFAULT_SAMPLE_RATE,
+            rate => rate,
+        }
+    }
+
+    /// Factory preset by name (case-insensitive); already sanitized
+    pub fn builtin(name: &str) -> Option<Config> {
+        let base = Config::default();
+        let contiguous = |gains: [f32; 3]| {
+            vec![
+                Band::new("Low", [20.0, 250.0], gains[0]),
+                Band::new("Mid", [250.0, 2500.0], gains[1]),
+                Band::new("High", [2500.0, 10000.0], gains[2]),
+            ]
+        };
+        let mut cfg = match name.trim().to_lowercase().as_str() {
+            // Three contiguous bands spanning 20 Hz–10 kHz so the color comes through untouched
+            "white" => Config {
+                noise_color: NoiseColor::White,
+                volume: 0.7,
+                bands: contiguous([1.0, 1.0, 1.0]),
+                ..base
+            },
+            "pink" => Config {
+                noise_color: NoiseColor::Pink,
+                bands: contiguous([1.0, 1.0, 1.0]),
+                ..base
+            },
+            "brown" => base,
+            "deep brown" => Config {
+                brown_cutoff_hz: 7.6,
+                tilt_db_per_octave: -1.5,
+                bands: vec![
+                    Band::new("Low", [10.0, 250.0], 1.5),
+                    Band::new("Mid", [250.0, 500.0], 0.15),
+                ],
+                bass_boost: 4.0,
+                ..base
+            },
+            // Hiss-forward pink: soft floor, bright splash on top
+            "rain" => Config {
+                noise_color: NoiseColor::Pink,
+                tilt_db_per_octave: 1.0,
+                bands: vec![
+                    Band::new("Low", [20.0, 300.0], 0.4),
+                    Band::new("Mid", [300.0, 3000.0], 0.6),
+                    Band::new("High", [3000.0, 10000.0], 1.0),
+                ],
+                ..base
+            },
+            // Brown with a broad low-mid hump, like blades moving air
+            "fan" => Config {
+                brown_cutoff_hz: 77.0,
+                bands: vec![
+                    Band::new("Low", [20.0, 80.0], 1.0),
+                    Band::new("Mid", [80.0, 400.0], 1.0),
+                    Band::new("High", [400.0, 3000.0], 0.2),
+                ],
+                bass_boost: 2.0,
+                ..base
+            },
+            // Energy concentrated around 1.5–6 kHz, like a parent's "shhh"
+            "baby shush" => Config {
+                noise_color: NoiseColor::Pink,
+                bands: vec![
+                    Band::new("Mid", [400.0, 1500.0], 0.5),
+                    Band::new("High", [1500.0, 6000.0], 1.25),
+                ],
+                ..base
+            },
+            _ => return None,
+        };
+        cfg.sanitize();
+        Some(cfg)
+    }
+
+    pub fn is_builtin(name: &str) -> bool {
+        BUILTIN_PRESETS
+            .iter()
+            .any(|b| b.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Parse config TOML, upgrading files from the fixed low/mid/high layout
+    /// and the old `alpha` coefficient
+    pub fn from_toml(content: &str) -> Result<Config> {
+        let mut table: toml::Table = toml::from_str(content)?;
+        migrate_legacy_bands(&mut table)?;
+        migrate_alpha(&mut table);
+        Ok(table.try_into()?)
+    }
+
+    /// Clamp all fields to sane operating ranges
     pub fn sanitize(&mut self) {
         self.volume = self.volume.clamp(0.0, 1.0);
-        self.alpha = self.alpha.clamp(0.9, 0.9999);
-
-        #[inline]
-        fn clamp_pair(p: &mut [f32; 2], lo: f32, hi: f32) {
-            p[0] = p[0].clamp(lo, hi);
-            p[1] = p[1].clamp(lo, hi);
-            if p[0] > p[1] {
-                p.swap(0, 1);
-            }
+        self.fade_ms = self.fade_ms.clamp(0.0, 2000.0);
+        if self.sample_rate != 0 {
+            self.sample_rate = self.sample_rate.clamp(MIN_SAMPLE_RATE, MAX_SAMPLE_RATE);
         }
+        self.buffer_frames = self.buffer_frames.clamp(64, 8192);
+        self.brown_cutoff_hz = self.brown_cutoff_hz.clamp(0.5, 800.0);
+        self.tilt_db_per_octave = self.tilt_db_per_octave.clamp(-6.0, 6.0);
+        self.stereo_width = self.stereo_width.clamp(0.0, 2.0);
+        self.balance = self.balance.clamp(-1.0, 1.0);
 
-        // clamp each band's min and max
-        clamp_pair(&mut self.band_low, 1.0, 1000.0);
-        clamp_pair(&mut self.band_mid, 1.0, 5000.0);
-        clamp_pair(&mut self.band_high, 1.0, 10000.0);
-
-        // Prevents inverse band ranges
-        if self.band_low[1] > self.band_mid[0] {
-            self.band_mid[0] = self.band_low[1].clamp(1.0, 5000.0);
-            if self.band_mid[0] > self.band_mid[1] {
-                self.band_mid[1] = self.band_mid[0];
+        // bands: bounded count, ordered edges, a name for each
+        self.bands.truncate(MAX_BANDS);
+        for (i, band) in self.bands.iter_mut().enumerate() {
+            band.name = band.name.trim().to_string();
+            if band.name.is_empty() {
+                band.name = format!("Band {}", i + 1);
             }
+            band.range[0] = band.range[0].clamp(1.0, 20000.0);
+            band.range[1] = band.range[1].clamp(1.0, 20000.0);
+            if band.range[0] > band.range[1] {
+                band.range.swap(0, 1);
+            }
+            band.gain = band.gain.clamp(0.0, 10.0);
+            band.pan = band.pan.clamp(-1.0, 1.0);
         }
-        if self.band_mid[1] > self.band_high[0] {
-            self.band_high[0] = self.band_mid[1].clamp(1.0, 10000.0);
-            if self.band_high[0] > self.band_high[1] {
-                self.band_high[1] = self.band_high[0];
+
+        // lfos: bounded count; a band target past the end falls back to the first band
+        self.lfos.truncate(MAX_LFOS);
+        for lfo in &mut self.lfos {
+            lfo.rate = lfo.rate.clamp(0.01, 5.0);
+            lfo.depth = lfo.depth.clamp(0.0, 1.0);
+            if lfo.band >= self.bands.len() {
+                lfo.band = 0;
             }
         }
 
-        // per-band volume + bass boost
-        self.band_volume_low = self.band_volume_low.clamp(0.0, 10.0);
-        self.band_volume_mid = self.band_volume_mid.clamp(0.0, 10.0);
-        self.band_volume_high = self.band_volume_high.clamp(0.0, 10.0);
+        self.layers.truncate(MAX_LAYERS);
+        for layer in &mut self.layers {
+            layer.path = layer.path.trim().to_string();
+            layer.gain = layer.gain.clamp(0.0, 2.0);
+        }
+
         self.bass_boost = self.bass_boost.clamp(0.0, 10.0);
+        self.bass_boost_freq = self.bass_boost_freq.clamp(20.0, 500.0);
+        self.bass_boost_slope = self.bass_boost_slope.clamp(0.1, 1.0);
 
         // seed range
         self.seed = self.seed.min(65535);
+
+        // sleep timer; the fade can't be longer than the timer itself
+        self.sleep_minutes = self.sleep_minutes.clamp(15.0, 480.0);
+        self.sleep_fade_minutes = self
+            .sleep_fade_minutes
+            .clamp(0.0, 60.0)
+            .min(self.sleep_minutes);
+
+        self.soundscape_level = self.soundscape_level.clamp(0.0, 1.0);
+
+        self.tone_carrier = self.tone_carrier.clamp(40.0, 1000.0);
+        self.tone_beat = self.tone_beat.clamp(0.5, 40.0);
+        self.tone_duty = self.tone_duty.clamp(0.1, 0.9);
+        self.tone_level = self.tone_level.clamp(0.0, 1.0);
+    }
+}
+
+// -------------------------
+// Shared config
+// -------------------------
+
+/// The live config, shared by the UI and the file watcher. Changes made through
+/// `lock()` are published to the audio thread's `Reader` when the guard drops,
+/// so the audio thread itself never takes this lock.
+#[derive(Clone)]
+pub struct SharedConfig {
+    inner: Arc<Mutex<SharedInner>>,
+}
+
+struct SharedInner {
+    cfg: Config,
+    published: Config,
+    writer: Writer<Config>,
+    loader: Option<SampleLoader>,
+}
+
+pub struct ConfigGuard<'a>(MutexGuard<'a, SharedInner>);
+
+impl SharedConfig {
+    pub fn new(cfg: Config) -> (Self, Reader<Config>) {
+        let (writer, reader) = triple_buffer(cfg.clone());
+        let inner = SharedInner {
+            published: cfg.clone(),
+            cfg,
+            writer,
+            loader: None,
+        };
+        (
+            Self {
+                inner: Arc::new(Mutex::new(inner)),
+            },
+            reader,
+        )
+    }
+
+    pub fn lock(&self) -> ConfigGuard<'_> {
+        ConfigGuard(self.inner.lock().unwrap())
+    }
+
+    /// Hand layer file changes to `loader` from now on, starting with the current ones
+    pub fn attach_loader(&self, loader: SampleLoader) {
+        let mut inner = self.inner.lock().unwrap();
+        loader.load(layer_paths(&inner.cfg));
+        inner.loader = Some(loader);
+    }
+}
+
+impl Deref for ConfigGuard<'_> {
+    type Target = Config;
+    fn deref(&self) -> &Config {
+        &self.0.cfg
+    }
+}
+
+impl DerefMut for ConfigGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Config {
+        &mut self.0.cfg
+    }
+}
+
+fn layer_paths(cfg: &Config) -> Vec<String> {
+    cfg.layers.iter().map(|l| l.path.clone()).collect()
+}
+
+impl Drop for ConfigGuard<'_> {
+    fn drop(&mut self) {
+        let inner = &mut *self.0;
+        if inner.cfg != inner.published {
+            // Only new files need decoding; gain and toggles travel with the config
+            if let Some(loader) = &inner.loader
+                && layer_paths(&inner.cfg) != layer_paths(&inner.published)
+            {
+                loader.load(layer_paths(&inner.cfg));
+            }
+            inner.writer.publish(&inner.cfg);
+            inner.published.clone_from(&inner.cfg);
+        }
+    }
+}
+
+/// Turn `band_low`/`enable_low`/`band_volume_low`/... keys into a `bands` list.
+/// Ranges get the old clamping and ordering, and the mid/high half-level mix is folded
+/// into their gains, so nothing changes audibly.
+fn migrate_legacy_bands(table: &mut toml::Table) -> Result<()> {
+    fn take<T: serde::de::DeserializeOwned>(table: &mut toml::Table, key: &str) -> Option<T> {
+        table.remove(key).and_then(|v| v.try_into().ok())
+    }
+
+    let legacy = LEGACY_BANDS
+        .iter()
+        .any(|(key, ..)| table.contains_key(&format!("band_{key}")));
+    let mut bands = Vec::new();
+    for (key, name, range, volume, mix) in LEGACY_BANDS {
+        let mut band = Band::new(name, range, volume * mix);
+        if let Some(r) = take(table, &format!("band_{key}")) {
+            band.range = r;
+        }
+        if let Some(v) = take::<f32>(table, &format!("band_volume_{key}")) {
+            band.gain = v * mix;
+        }
+        if let Some(e) = take(table, &format!("enable_{key}")) {
+            band.enabled = e;
+        }
+        if let Some(f) = take(table, &format!("band_filter_{key}")) {
+            band.filter = f;
+        }
+        if let Some(s) = take(table, &format!("band_slope_{key}")) {
+            band.slope = s;
+        }
+        bands.push(band);
+    }
+
+    let mut ranges = [bands[0].range, bands[1].range, bands[2].range];
+    order_legacy_ranges(&mut ranges);
+    for (band, range) in bands.iter_mut().zip(ranges) {
+        band.range = range;
+    }
+
+    if legacy && !table.contains_key("bands") {
+        table.insert("bands".into(), toml::Value::try_from(bands)?);
+    }
+    Ok(())
+}
+
+/// Turn the old `alpha` integrator coefficient into `brown_cutoff_hz`.
+/// Alpha was applied per sample at 48 kHz, so fc = -ln(alpha) * 48000 / 2π.
+fn migrate_alpha(table: &mut toml::Table) {
+    let Some(alpha) = table.remove("alpha").and_then(|v| v.try_into::<f64>().ok()) else {
+        return;
+    };
+    if !table.contains_key("brown_cutoff_hz") {
+        let alpha = alpha.clamp(0.9, 0.9999);
+        let hz = -alpha.ln() * DEFAULT_SAMPLE_RATE as f64 / std::f64::consts::TAU;
+        // Two decimals keep the rewritten config readable
+        let hz = (hz * 100.0).round() / 100.0;
+        table.insert("brown_cutoff_hz".into(), toml::Value::Float(hz));
     }
 }
 
 //Create the config
-pub fn load_or_create_config<P: AsRef<std::path::Path>>(path: P) -> Result<Config> {
+pub fn load_or_create_config<P: AsRef<Path>>(path: P) -> Result<Config> {
     use std::fs;
     let path = path.as_ref();
 
@@ -116,7 +915,7 @@ pub fn load_or_create_config<P: AsRef<std::path::Path>>(path: P) -> Result<Confi
     }
 
     let mut cfg = if let Ok(content) = fs::read_to_string(path) {
-        toml::from_str::<Config>(&content).map_err(anyhow::Error::from)?
+        Config::from_toml(&content)?
     } else {
         let cfg = Config::default();
         let toml = toml::to_string_pretty(&cfg)?;
@@ -128,3 +927,145 @@ pub fn load_or_create_config<P: AsRef<std::path::Path>>(path: P) -> Result<Confi
     cfg.sanitize();
     Ok(cfg)
 }
+
+/// Write `cfg` to `path`, remembering the text so the watcher can skip our own writes
+pub fn save_config(path: &Path, cfg: &Config, last_saved: &Mutex<String>) {
+    if let Ok(toml) = toml::to_string_pretty(cfg) {
+        let mut last = last_saved.lock().unwrap();
+        if std::fs::write(path, &toml).is_ok() {
+            *last = toml;
+        }
+    }
+}
+
+/// Watch `path` for external edits and hand each new, sanitized config to `on_change`.
+/// The parent directory is watched so editors that save via rename are still seen.
+pub fn watch_config<F>(
+    path: PathBuf,
+    last_saved: Arc<Mutex<String>>,
+    on_change: F,
+) -> Result<notify::RecommendedWatcher>
+where
+    F: Fn(Config) + Send + 'static,
+{
+    let dir = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let file_name = path.file_name().map(|n| n.to_os_string());
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        if !event
+            .paths
+            .iter()
+            .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name)
+        {
+            return;
+        }
+
+        let mut cfg = {
+            // Read under the lock so a save can't land between the read and the compare
+            let mut last = last_saved.lock().unwrap();
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                return;
+            };
+            // Our own write (or an event for content we already applied)
+            if *last == content {
+                return;
+            }
+            match Config::from_toml(&content) {
+                Ok(cfg) => {
+                    *last = content;
+                    cfg
+                }
+                // Editors may save in several steps; the next event gets the full file
+                Err(e) => {
+                    eprintln!("warning: ignoring invalid {}: {e}", path.display());
+                    return;
+                }
+            }
+        };
+
+        cfg.sanitize();
+        println!("Reloaded {}", path.display());
+        on_change(cfg);
+    })?;
+
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // config.toml as written before bands and the brown cutoff existed
+    const BASELINE: &str = r#"
+volume = 0.8
+enable_low = true
+enable_mid = false
+enable_high = true
+alpha = 0.996
+band_low = [20.0, 400.0]
+band_mid = [100.0, 2000.0]
+band_high = [1500.0, 8000.0]
+band_volume_low = 1.0
+band_volume_mid = 0.8
+band_volume_high = 0.6
+seed = 42
+random = false
+bass_boost = 0.0
+"#;
+
+    #[test]
+    fn legacy_bands_become_a_band_list() {
+        let cfg = Config::from_toml(BASELINE).unwrap();
+        let names: Vec<&str> = cfg.bands.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["Low", "Mid", "High"]);
+        let enabled: Vec<bool> = cfg.bands.iter().map(|b| b.enabled).collect();
+        assert_eq!(enabled, [true, false, true]);
+        // Mid and high were mixed in at half level
+        let gains: Vec<f32> = cfg.bands.iter().map(|b| b.gain).collect();
+        assert_eq!(gains, [1.0, 0.4, 0.3]);
+        // Each band starts where the one below it ends, as the fixed bands did
+        let ranges: Vec<[f32; 2]> = cfg.bands.iter().map(|b| b.range).collect();
+        assert_eq!(ranges, [[20.0, 400.0], [400.0, 2000.0], [2000.0, 8000.0]]);
+    }
+
+    #[test]
+    fn legacy_keys_leave_an_existing_band_list_alone() {
+        let cfg = Config {
+            bands: vec![Band::new("Only", [50.0, 60.0], 0.7)],
+            ..Config::default()
+        };
+        let text = format!(
+            "band_mid = [1.0, 2.0]\nenable_mid = false\n{}",
+            toml::to_string(&cfg).unwrap()
+        );
+        assert_eq!(Config::from_toml(&text).unwrap().bands, cfg.bands);
+    }
+
+    #[test]
+    fn default_bands_follow_the_legacy_ordering() {
+        let ranges: Vec<[f32; 2]> = default_bands().iter().map(|b| b.range).collect();
+        assert_eq!(ranges, [[10.0, 500.0], [500.0, 1000.0], [1000.0, 10000.0]]);
+    }
+
+    #[test]
+    fn alpha_becomes_a_brown_cutoff() {
+        // -ln(0.996) * 48000 / 2π
+        let cfg = Config::from_toml(BASELINE).unwrap();
+        assert!(
+            (cfg.brown_cutoff_hz - 30.62).abs() < 0.01,
+            "{}",
+            cfg.brown_cutoff_hz
+        );
+
+        let explicit = format!("{BASELINE}brown_cutoff_hz = 12.0\n");
+        assert_eq!(Config::from_toml(&explicit).unwrap().brown_cutoff_hz, 12.0);
+    }
+}
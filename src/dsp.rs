@@ -1,27 +1,15 @@
 use anyhow::Result;
-use rand::{Rng, SeedableRng, rngs::SmallRng};
 use std::sync::{
-    Arc, Mutex,
-    atomic::{AtomicBool, Ordering},
+    Arc,
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
 };
+use std::time::{Duration, Instant};
 
-use crate::bass_boost::BassBoost;
-use crate::biquad::Biquad;
 use crate::config::Config;
-
-// -------------------------
-// Thread-local DSP State
-// -------------------------
-std::thread_local! {
-    static FILTERS_L: std::cell::RefCell<Option<(Biquad, Biquad, Biquad)>> = std::cell::RefCell::new(None);
-    static FILTERS_R: std::cell::RefCell<Option<(Biquad, Biquad, Biquad)>> = std::cell::RefCell::new(None);
-
-    static BASS_BOOST_L: std::cell::RefCell<BassBoost> = std::cell::RefCell::new(BassBoost::new(48000.0));
-    static BASS_BOOST_R: std::cell::RefCell<BassBoost> = std::cell::RefCell::new(BassBoost::new(48000.0));
-
-    static NOISE_L: std::cell::RefCell<f32> = std::cell::RefCell::new(0.0);
-    static NOISE_R: std::cell::RefCell<f32> = std::cell::RefCell::new(0.0);
-}
+use crate::engine::{CHANNELS, NoiseEngine};
+use crate::sample::SampleLoader;
+use crate::sleep::{SleepController, SleepTimer};
+use crate::triple_buffer::Reader;
 
 // -------------------------
 // Mute Controller
@@ -29,6 +17,7 @@ std::thread_local! {
 #[derive(Clone)]
 pub struct MuteController {
     muted: Arc<AtomicBool>,
+    silent: Arc<AtomicBool>,
 }
 
 impl MuteController {
@@ -40,151 +29,235 @@ impl MuteController {
     pub fn is_muted(&self) -> bool {
         self.muted.load(Ordering::Relaxed)
     }
+    /// True once a mute has finished fading and the output is silent
+    pub fn is_silent(&self) -> bool {
+        self.silent.load(Ordering::Relaxed)
+    }
+    /// Mute and block until the fade-out has finished (or `timeout` passes)
+    pub fn fade_out(&self, timeout: Duration) {
+        self.muted.store(true, Ordering::Relaxed);
+        let start = Instant::now();
+        while !self.is_silent() && start.elapsed() < timeout {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+// -------------------------
+// Callback timing
+// -------------------------
+
+// Weight of each new callback in the running average render time
+const AVG_WEIGHT: f32 = 0.05;
+
+#[derive(Default)]
+struct StatsShared {
+    callbacks: AtomicU64,
+    late: AtomicU64,
+    // Microseconds: playback time of the last buffer, and render time average/peak
+    buffer_us: AtomicU32,
+    avg_us: AtomicU32,
+    peak_us: AtomicU32,
+    reset: AtomicBool,
+}
+
+/// Snapshot of how long the audio callback takes against its deadline
+pub struct CallbackStats {
+    /// Playback time of one buffer; a callback has this long to render the next
+    pub buffer_ms: f32,
+    pub avg_ms: f32,
+    pub peak_ms: f32,
+    pub callbacks: u64,
+    /// Callbacks that overran `buffer_ms`, each one a likely dropout
+    pub late: u64,
+}
+
+#[derive(Clone)]
+pub struct StatsController {
+    shared: Arc<StatsShared>,
+}
+
+impl StatsController {
+    pub fn snapshot(&self) -> CallbackStats {
+        let ms = |v: &AtomicU32| v.load(Ordering::Relaxed) as f32 / 1000.0;
+        let s = &self.shared;
+        CallbackStats {
+            buffer_ms: ms(&s.buffer_us),
+            avg_ms: ms(&s.avg_us),
+            peak_ms: ms(&s.peak_us),
+            callbacks: s.callbacks.load(Ordering::Relaxed),
+            late: s.late.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Clear the counters and peak (applied on the next callback)
+    pub fn reset(&self) {
+        self.shared.reset.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Audio side: times each callback and publishes the running figures
+struct CallbackTimer {
+    shared: Arc<StatsShared>,
+    sample_rate: f32,
+    avg_us: f32,
+    peak_us: u32,
+    callbacks: u64,
+    late: u64,
+}
+
+impl CallbackTimer {
+    fn new(sample_rate: f32) -> (Self, StatsController) {
+        let shared = Arc::new(StatsShared::default());
+        let timer = Self {
+            shared: shared.clone(),
+            sample_rate,
+            avg_us: 0.0,
+            peak_us: 0,
+            callbacks: 0,
+            late: 0,
+        };
+        (timer, StatsController { shared })
+    }
+
+    fn record(&mut self, elapsed: Duration, frames: usize) {
+        if self.shared.reset.swap(false, Ordering::Relaxed) {
+            self.avg_us = 0.0;
+            self.peak_us = 0;
+            self.callbacks = 0;
+            self.late = 0;
+        }
+
+        // Backends may hand over a different size than requested, so go by this buffer
+        let budget_us = (frames as f32 / self.sample_rate * 1e6) as u32;
+        let us = elapsed.as_micros().min(u32::MAX as u128) as u32;
+        self.avg_us = if self.callbacks == 0 {
+            us as f32
+        } else {
+            self.avg_us + (us as f32 - self.avg_us) * AVG_WEIGHT
+        };
+        self.peak_us = self.peak_us.max(us);
+        self.callbacks += 1;
+        if us > budget_us {
+            self.late += 1;
+        }
+
+        let s = &self.shared;
+        s.buffer_us.store(budget_us, Ordering::Relaxed);
+        s.avg_us.store(self.avg_us as u32, Ordering::Relaxed);
+        s.peak_us.store(self.peak_us, Ordering::Relaxed);
+        s.callbacks.store(self.callbacks, Ordering::Relaxed);
+        s.late.store(self.late, Ordering::Relaxed);
+    }
+}
+
+// -------------------------
+// Play/pause gain ramp
+// -------------------------
+struct FadeRamp {
+    gain: f32,
+}
+
+impl FadeRamp {
+    /// Ramp interleaved `buffer` towards `target` (0.0 or 1.0) over `fade_ms`
+    fn apply(&mut self, buffer: &mut [f32], target: f32, fade_ms: f32, sample_rate: f32) {
+        let fade_frames = fade_ms * 0.001 * sample_rate;
+        let step = if fade_frames < 1.0 {
+            1.0
+        } else {
+            1.0 / fade_frames
+        };
+
+        for frame in buffer.chunks_mut(CHANNELS) {
+            if self.gain < target {
+                self.gain = (self.gain + step).min(target);
+            } else if self.gain > target {
+                self.gain = (self.gain - step).max(target);
+            }
+            for s in frame.iter_mut() {
+                *s *= self.gain;
+            }
+        }
+    }
 }
 
 // -------------------------
 // Init audio stream
 // -------------------------
 pub fn init_stream(
-    shared_cfg: Arc<Mutex<Config>>,
-    rng_seeded: &mut SmallRng,
-) -> Result<(tinyaudio::OutputDevice, MuteController)> {
-    let channels = 2;
-
-    let mut rng = SmallRng::from_rng(rng_seeded);
-    let samplerate = 48000.0;
+    mut cfg_rx: Reader<Config>,
+    seed: u64,
+) -> Result<(
+    tinyaudio::OutputDevice,
+    MuteController,
+    SleepController,
+    SampleLoader,
+    StatsController,
+)> {
+    let samplerate = cfg_rx.current().output_sample_rate() as f32;
 
     let params = tinyaudio::OutputDeviceParameters {
         sample_rate: samplerate as usize,
-        channels_count: channels,
-        channel_sample_count: 1024,
+        channels_count: CHANNELS,
+        channel_sample_count: cfg_rx.current().buffer_frames as usize,
     };
 
     let muted = Arc::new(AtomicBool::new(false));
+    let silent = Arc::new(AtomicBool::new(false));
     let mute_ctrl = MuteController {
         muted: muted.clone(),
+        silent: silent.clone(),
     };
 
+    let mut engine = NoiseEngine::new(cfg_rx.current(), samplerate, seed);
+    // Layer files are decoded on their own thread, at the rate the engine runs at
+    let (loader, mut layers_rx) = SampleLoader::spawn(samplerate);
+    let (mut sleep_timer, sleep_ctrl) = SleepTimer::new(samplerate, cfg_rx.current().sleep_minutes);
+    let (mut callback_timer, stats_ctrl) = CallbackTimer::new(samplerate);
+    // Start from silence so the first buffer fades in too
+    let mut ramp = FadeRamp { gain: 0.0 };
+
     let device = match tinyaudio::run_output_device(params, move |buffer: &mut [f32]| {
-        if muted.load(Ordering::Relaxed) {
+        let start = Instant::now();
+        let target = if muted.load(Ordering::Relaxed) {
+            0.0
+        } else {
+            1.0
+        };
+
+        // Fully faded out: nothing to render
+        if target == 0.0 && ramp.gain == 0.0 {
             for s in buffer.iter_mut() {
                 *s = 0.0;
             }
-            return;
-        }
+            silent.store(true, Ordering::Relaxed);
+        } else {
+            silent.store(false, Ordering::Relaxed);
 
-        let cfg = shared_cfg.lock().unwrap().clone();
-        let alpha = cfg.alpha.clamp(0.9, 0.9999);
-
-        // Ensure filters exist & update coefficients
-        for filter_ref in [&FILTERS_L, &FILTERS_R] {
-            filter_ref.with(|f| {
-                let mut filters = f.borrow_mut();
-                if filters.is_none() {
-                    let mut low = Biquad::new();
-                    let mut mid = Biquad::new();
-                    let mut high = Biquad::new();
-                    low.update_bandpass(samplerate, cfg.band_low[0], cfg.band_low[1]);
-                    mid.update_bandpass(samplerate, cfg.band_mid[0], cfg.band_mid[1]);
-                    high.update_bandpass(samplerate, cfg.band_high[0], cfg.band_high[1]);
-                    *filters = Some((low, mid, high));
-                } else {
-                    let (low, mid, high) = &mut *filters.as_mut().unwrap();
-                    low.update_bandpass(samplerate, cfg.band_low[0], cfg.band_low[1]);
-                    mid.update_bandpass(samplerate, cfg.band_mid[0], cfg.band_mid[1]);
-                    high.update_bandpass(samplerate, cfg.band_high[0], cfg.band_high[1]);
-                }
-            });
+            // Pick up UI edits without locking; only when something changed
+            if let Some(cfg) = cfg_rx.update() {
+                engine.set_config(cfg);
+            }
+            if let Some(buffers) = layers_rx.update() {
+                engine.set_samples(buffers);
+            }
+            engine.render(buffer);
+
+            // Sleep timer fades the master gain, then pauses playback
+            if sleep_timer.apply(cfg_rx.current(), buffer, CHANNELS) {
+                muted.store(true, Ordering::Relaxed);
+                // The sleep fade already reached silence; don't fade out again from full gain
+                ramp.gain = 0.0;
+            } else {
+                ramp.apply(buffer, target, cfg_rx.current().fade_ms, samplerate);
+            }
         }
 
-        // Bass boost tracks cfg
-        BASS_BOOST_L.with(|bb| bb.borrow_mut().set_boost(cfg.bass_boost));
-        BASS_BOOST_R.with(|bb| bb.borrow_mut().set_boost(cfg.bass_boost));
-
-        // Noise + filters
-        NOISE_L.with(|bl| {
-            NOISE_R.with(|br| {
-                let mut brown_l = *bl.borrow();
-                let mut brown_r = *br.borrow();
-
-                for frame in buffer.chunks_mut(channels) {
-                    let white_l = rng.random_range(-1.0..1.0);
-                    let white_r = rng.random_range(-1.0..1.0);
-
-                    brown_l = (1.0 - alpha) * white_l + alpha * brown_l;
-                    brown_r = (1.0 - alpha) * white_r + alpha * brown_r;
-
-                    let (low_l, mid_l, high_l) = FILTERS_L.with(|f| {
-                        let mut filters = f.borrow_mut();
-                        let (low_f, mid_f, high_f) = &mut *filters.as_mut().unwrap();
-                        (
-                            if cfg.enable_low {
-                                low_f.process(brown_l) * cfg.band_volume_low
-                            } else {
-                                0.0
-                            },
-                            if cfg.enable_mid {
-                                mid_f.process(brown_l) * cfg.band_volume_mid
-                            } else {
-                                0.0
-                            },
-                            if cfg.enable_high {
-                                high_f.process(brown_l) * cfg.band_volume_high
-                            } else {
-                                0.0
-                            },
-                        )
-                    });
-
-                    let (low_r, mid_r, high_r) = FILTERS_R.with(|f| {
-                        let mut filters = f.borrow_mut();
-                        let (low_f, mid_f, high_f) = &mut *filters.as_mut().unwrap();
-                        (
-                            if cfg.enable_low {
-                                low_f.process(brown_r) * cfg.band_volume_low
-                            } else {
-                                0.0
-                            },
-                            if cfg.enable_mid {
-                                mid_f.process(brown_r) * cfg.band_volume_mid
-                            } else {
-                                0.0
-                            },
-                            if cfg.enable_high {
-                                high_f.process(brown_r) * cfg.band_volume_high
-                            } else {
-                                0.0
-                            },
-                        )
-                    });
-
-                    let low_boost_l = if cfg.enable_low {
-                        BASS_BOOST_L.with(|bb| bb.borrow_mut().process(low_l))
-                    } else {
-                        0.0
-                    };
-
-                    let low_boost_r = if cfg.enable_low {
-                        BASS_BOOST_R.with(|bb| bb.borrow_mut().process(low_r))
-                    } else {
-                        0.0
-                    };
-
-                    let mixed_l = (mid_l + high_l) * 0.5 + low_boost_l;
-                    let mixed_r = (mid_r + high_r) * 0.5 + low_boost_r;
-
-                    frame[0] = (mixed_l * cfg.volume).clamp(-1.0, 1.0);
-                    frame[1] = (mixed_r * cfg.volume).clamp(-1.0, 1.0);
-                }
-
-                *bl.borrow_mut() = brown_l;
-                *br.borrow_mut() = brown_r;
-            });
-        });
+        callback_timer.record(start.elapsed(), buffer.len() / CHANNELS);
     }) {
         Ok(dev) => dev,
         Err(e) => return Err(anyhow::anyhow!("tinyaudio failed: {}", e)),
     };
 
-    Ok((device, mute_ctrl))
+    Ok((device, mute_ctrl, sleep_ctrl, loader, stats_ctrl))
 }
@@ -0,0 +1,154 @@
+use std::f32::consts::{PI, TAU};
+
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+use crate::config::{Config, Lfo, LfoShape, LfoTarget, MAX_BANDS, MAX_LFOS};
+use crate::smooth::Smoothed;
+
+// Depth eases in and out so toggling or removing an LFO doesn't jump
+const DEPTH_RAMP_SECS: f32 = 0.2;
+// Full depth swings a band center this many octaves either way
+const MAX_SHIFT_OCTAVES: f32 = 2.0;
+// Keeps the LFO random stream apart from the noise RNG seeded with the same value
+const SEED_SALT: u64 = 0x4c46_4f73;
+
+/// Where the LFOs currently put each target, recomputed every control block
+#[derive(Clone, Copy)]
+pub struct Modulation {
+    /// Master gain multiplier
+    pub volume: f32,
+    /// Gain multiplier per band slot
+    pub gain: [f32; MAX_BANDS],
+    /// Edge shift per band slot, in octaves
+    pub shift: [f32; MAX_BANDS],
+}
+
+impl Modulation {
+    pub const NONE: Modulation = Modulation {
+        volume: 1.0,
+        gain: [1.0; MAX_BANDS],
+        shift: [0.0; MAX_BANDS],
+    };
+}
+
+fn lfo_depth(lfo: &Lfo) -> f32 {
+    if lfo.enabled { lfo.depth } else { 0.0 }
+}
+
+struct Oscillator {
+    shape: LfoShape,
+    target: LfoTarget,
+    band: usize,
+    rate: f32,
+    depth: Smoothed,
+    // Position in the cycle (0.0–1.0)
+    phase: f32,
+    // Random shape: eases from `from` to `to` over each cycle
+    from: f32,
+    to: f32,
+}
+
+impl Oscillator {
+    fn new(sample_rate: f32, lfo: Option<&Lfo>, rng: &mut SmallRng) -> Self {
+        let mut osc = Self {
+            shape: LfoShape::Sine,
+            target: LfoTarget::Volume,
+            band: 0,
+            rate: 0.1,
+            depth: Smoothed::new(lfo.map_or(0.0, lfo_depth), sample_rate, DEPTH_RAMP_SECS),
+            phase: 0.0,
+            from: rng.random_range(-1.0..1.0),
+            to: rng.random_range(-1.0..1.0),
+        };
+        if let Some(lfo) = lfo {
+            osc.set(lfo);
+        }
+        osc
+    }
+
+    fn set(&mut self, lfo: &Lfo) {
+        self.shape = lfo.shape;
+        self.target = lfo.target;
+        self.band = lfo.band;
+        self.rate = lfo.rate;
+        self.depth.set_target(lfo_depth(lfo));
+    }
+
+    /// Fade out a slot that no longer has an LFO behind it
+    fn release(&mut self) {
+        self.depth.set_target(0.0);
+    }
+
+    /// Current output in -1.0..=1.0
+    fn value(&self) -> f32 {
+        match self.shape {
+            LfoShape::Sine => (self.phase * TAU).sin(),
+            LfoShape::Triangle => 1.0 - 4.0 * (self.phase - 0.5).abs(),
+            LfoShape::Random => {
+                let ease = 0.5 - 0.5 * (self.phase * PI).cos();
+                self.from + (self.to - self.from) * ease
+            }
+        }
+    }
+
+    fn advance(&mut self, seconds: f32, rng: &mut SmallRng) {
+        self.phase += self.rate * seconds;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.from = self.to;
+            self.to = rng.random_range(-1.0..1.0);
+        }
+    }
+}
+
+/// Fixed set of LFOs, stepped at control rate. Doesn't allocate after creation.
+pub struct LfoBank {
+    sample_rate: f32,
+    rng: SmallRng,
+    slots: [Oscillator; MAX_LFOS],
+}
+
+impl LfoBank {
+    pub fn new(cfg: &Config, sample_rate: f32, seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed ^ SEED_SALT);
+        let slots =
+            std::array::from_fn(|i| Oscillator::new(sample_rate, cfg.lfos.get(i), &mut rng));
+        Self {
+            sample_rate,
+            rng,
+            slots,
+        }
+    }
+
+    pub fn set_config(&mut self, cfg: &Config) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            match cfg.lfos.get(i) {
+                Some(lfo) => slot.set(lfo),
+                None => slot.release(),
+            }
+        }
+    }
+
+    /// Step every oscillator by `frames` and return the combined modulation
+    pub fn advance(&mut self, frames: usize) -> Modulation {
+        let mut m = Modulation::NONE;
+        for slot in &mut self.slots {
+            let depth = slot.depth.advance(frames);
+            if depth > 0.0 {
+                let value = slot.value();
+                // Gains only ever dip, so modulation can't push the output into clipping
+                let dip = 1.0 - depth * 0.5 * (1.0 - value);
+                match slot.target {
+                    LfoTarget::Volume => m.volume *= dip,
+                    LfoTarget::Gain if slot.band < MAX_BANDS => m.gain[slot.band] *= dip,
+                    LfoTarget::Center if slot.band < MAX_BANDS => {
+                        m.shift[slot.band] += depth * value * MAX_SHIFT_OCTAVES
+                    }
+                    _ => {}
+                }
+            }
+            slot.advance(frames as f32 / self.sample_rate, &mut self.rng);
+        }
+        m
+    }
+}
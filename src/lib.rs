@@ -1,19 +1,48 @@
 use anyhow::{Context, Result};
-use rand::{Rng, SeedableRng, rngs::SmallRng};
-use slint::CloseRequestResponse;
+use rand::Rng;
+use slint::{CloseRequestResponse, Model};
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use std::path::PathBuf;
 #[allow(unused_imports)]
 use std::sync::OnceLock;
 
+mod bandpass;
 mod bass_boost;
 mod biquad;
+mod color;
 mod config;
 mod dsp;
-
-use config::{Config, load_or_create_config};
+mod engine;
+mod lfo;
+mod presets;
+mod render;
+mod sample;
+mod sleep;
+mod smooth;
+mod soundscape;
+mod tilt;
+mod tone;
+mod triple_buffer;
+mod wav;
+
+use config::{SharedConfig, load_or_create_config, save_config, watch_config};
 use dsp::init_stream;
+use presets::PresetStore;
+use sleep::SleepController;
+
+pub use biquad::Biquad;
+// Everything a `Config` is built from, so embedders can set it up in code
+pub use config::{
+    BUILTIN_PRESETS, Band, BandFilter, BandSlope, BoostTarget, Config, DEFAULT_SAMPLE_RATE,
+    FadeCurve, Lfo, LfoShape, LfoTarget, MAX_BANDS, MAX_LAYERS, MAX_LFOS, MAX_SAMPLE_RATE,
+    MIN_SAMPLE_RATE, NoiseColor, SampleLayer, Soundscape, ToneEnvelope, ToneMode,
+};
+pub use engine::{CHANNELS, NoiseEngine};
+pub use render::{RenderJob, run_render};
+pub use sample::{LayerBuffers, SampleBuffer};
 
 slint::include_modules!();
 
@@ -55,15 +84,271 @@ fn config_path() -> PathBuf {
     }
 }
 
+/// Rates offered by the settings picker; 0 is `DEFAULT_SAMPLE_RATE`
+const SAMPLE_RATE_CHOICES: [u32; 5] = [0, 44100, 48000, 88200, 96000];
+/// Callback sizes offered by the diagnostics picker
+const BUFFER_CHOICES: [u32; 6] = [128, 256, 512, 1024, 2048, 4096];
+
+/// Picker index for `value`, or -1 when it isn't one of `choices`
+fn choice_index(choices: &[u32], value: u32) -> i32 {
+    choices
+        .iter()
+        .position(|&c| c == value)
+        .map_or(-1, |i| i as i32)
+}
+
+/// Value behind a picker index; `None` leaves a hand-edited config value alone
+fn choice_value(choices: &[u32], index: i32) -> Option<u32> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| choices.get(i))
+        .copied()
+}
+
+/// Push every config value into the UI properties
+fn push_config_to_ui(ui: &RootUI, cfg: &Config) {
+    ui.set_volume(cfg.volume);
+    ui.set_fade_ms(cfg.fade_ms);
+    // A value typed into config.toml that isn't a choice leaves the picker blank
+    ui.set_sample_rate(choice_index(&SAMPLE_RATE_CHOICES, cfg.sample_rate));
+    ui.set_buffer_frames(choice_index(&BUFFER_CHOICES, cfg.buffer_frames));
+    ui.set_noise_color(cfg.noise_color.index());
+    ui.set_brown_cutoff(cfg.brown_cutoff_hz);
+    ui.set_tilt(cfg.tilt_db_per_octave);
+    ui.set_stereo_width(cfg.stereo_width);
+    ui.set_balance(cfg.balance);
+    ui.set_bass_boost(cfg.bass_boost);
+    ui.set_bass_boost_freq(cfg.bass_boost_freq);
+    ui.set_bass_boost_slope(cfg.bass_boost_slope);
+    ui.set_bass_boost_target(cfg.bass_boost_target.index());
+
+    ui.set_random_seed(cfg.random);
+    if !cfg.random {
+        ui.set_seed(cfg.seed as f32);
+    }
+
+    ui.set_sleep_timer(cfg.sleep_timer);
+    ui.set_sleep_minutes(cfg.sleep_minutes);
+    ui.set_sleep_fade_minutes(cfg.sleep_fade_minutes);
+    ui.set_sleep_fade_curve(cfg.sleep_fade_curve.index());
+
+    ui.set_soundscape(cfg.soundscape.index());
+    ui.set_soundscape_level(cfg.soundscape_level);
+
+    ui.set_tone(cfg.tone);
+    ui.set_tone_carrier(cfg.tone_carrier);
+    ui.set_tone_beat(cfg.tone_beat);
+    ui.set_tone_level(cfg.tone_level);
+    ui.set_tone_mode(cfg.tone_mode.index());
+    ui.set_tone_duty(cfg.tone_duty);
+    ui.set_tone_envelope(cfg.tone_envelope.index());
+
+    push_bands_to_ui(ui, &cfg.bands);
+    push_lfos_to_ui(ui, &cfg.lfos);
+    push_layers_to_ui(ui, &cfg.layers);
+}
+
+fn band_to_item(band: &Band) -> BandItem {
+    BandItem {
+        name: band.name.as_str().into(),
+        enabled: band.enabled,
+        lo: band.range[0],
+        hi: band.range[1],
+        gain: band.gain,
+        pan: band.pan,
+        filter: band.filter.index(),
+        slope: band.slope.index(),
+    }
+}
+
+fn item_to_band(item: &BandItem) -> Band {
+    Band {
+        name: item.name.to_string(),
+        enabled: item.enabled,
+        range: [item.lo, item.hi],
+        gain: item.gain,
+        pan: item.pan,
+        filter: BandFilter::from_index(item.filter),
+        slope: BandSlope::from_index(item.slope),
+    }
+}
+
+/// Sync a list model, editing rows in place so a slider mid-drag isn't recreated;
+/// `replace` installs a fresh model when the length changed
+fn sync_model<T: Clone + PartialEq + 'static>(
+    model: slint::ModelRc<T>,
+    items: Vec<T>,
+    replace: impl FnOnce(slint::ModelRc<T>),
+) {
+    if model.row_count() == items.len() {
+        for (i, item) in items.into_iter().enumerate() {
+            if model.row_data(i).as_ref() != Some(&item) {
+                model.set_row_data(i, item);
+            }
+        }
+    } else {
+        replace(slint::ModelRc::new(slint::VecModel::from(items)));
+    }
+}
+
+fn push_bands_to_ui(ui: &RootUI, bands: &[Band]) {
+    let items = bands.iter().map(band_to_item).collect();
+    sync_model(ui.get_bands(), items, |m| ui.set_bands(m));
+    refresh_band_names(ui);
+}
+
+/// Band names for the LFO band picker, taken from the band list model
+fn refresh_band_names(ui: &RootUI) {
+    let names: Vec<slint::SharedString> = ui.get_bands().iter().map(|b| b.name).collect();
+    ui.set_band_names(slint::ModelRc::new(slint::VecModel::from(names)));
+}
+
+fn lfo_to_item(lfo: &Lfo) -> LfoItem {
+    LfoItem {
+        enabled: lfo.enabled,
+        shape: lfo.shape.index(),
+        target: lfo.target.index(),
+        band: lfo.band as i32,
+        rate: lfo.rate,
+        depth: lfo.depth,
+    }
+}
+
+fn item_to_lfo(item: &LfoItem) -> Lfo {
+    Lfo {
+        enabled: item.enabled,
+        shape: LfoShape::from_index(item.shape),
+        target: LfoTarget::from_index(item.target),
+        band: item.band.max(0) as usize,
+        rate: item.rate,
+        depth: item.depth,
+    }
+}
+
+fn push_lfos_to_ui(ui: &RootUI, lfos: &[Lfo]) {
+    let items = lfos.iter().map(lfo_to_item).collect();
+    sync_model(ui.get_lfos(), items, |m| ui.set_lfos(m));
+}
+
+fn layer_to_item(layer: &SampleLayer) -> LayerItem {
+    LayerItem {
+        path: layer.path.as_str().into(),
+        enabled: layer.enabled,
+        gain: layer.gain,
+    }
+}
+
+fn item_to_layer(item: &LayerItem) -> SampleLayer {
+    SampleLayer {
+        path: item.path.to_string(),
+        enabled: item.enabled,
+        gain: item.gain,
+    }
+}
+
+fn push_layers_to_ui(ui: &RootUI, layers: &[SampleLayer]) {
+    let items = layers.iter().map(layer_to_item).collect();
+    sync_model(ui.get_layers(), items, |m| ui.set_layers(m));
+}
+
+/// Apply a typed-in value to one row of a list model. `field` is `<key>:<index>`;
+/// `apply` sets the member named by `key` and returns false for unknown keys.
+fn set_row_field<T: Clone + 'static>(
+    model: slint::ModelRc<T>,
+    field: &str,
+    apply: impl FnOnce(&str, &mut T) -> bool,
+) {
+    let Some((key, index)) = field.split_once(':') else {
+        return;
+    };
+    let Ok(i) = index.parse::<usize>() else {
+        return;
+    };
+    let Some(mut row) = model.row_data(i) else {
+        return;
+    };
+    if apply(key, &mut row) {
+        model.set_row_data(i, row);
+    }
+}
+
+/// Apply a typed-in band value; `field` is `band_lo:<index>`, `band_hi:<index>`, `band_gain:<index>` or `band_pan:<index>`
+fn set_band_field(ui: &RootUI, field: &str, value: f32) {
+    set_row_field(ui.get_bands(), field, |key, band: &mut BandItem| {
+        match key {
+            "band_lo" => band.lo = value.clamp(1.0, 20000.0),
+            "band_hi" => band.hi = value.clamp(1.0, 20000.0),
+            "band_gain" => band.gain = value.clamp(0.0, 10.0),
+            "band_pan" => band.pan = value.clamp(-1.0, 1.0),
+            _ => return false,
+        }
+        true
+    });
+}
+
+/// Apply a typed-in LFO value; `field` is `lfo_rate:<index>` or `lfo_depth:<index>`
+fn set_lfo_field(ui: &RootUI, field: &str, value: f32) {
+    set_row_field(ui.get_lfos(), field, |key, lfo: &mut LfoItem| {
+        match key {
+            "lfo_rate" => lfo.rate = value.clamp(0.01, 5.0),
+            "lfo_depth" => lfo.depth = value.clamp(0.0, 1.0),
+            _ => return false,
+        }
+        true
+    });
+}
+
+/// Apply a typed-in layer gain; `field` is `layer_gain:<index>`
+fn set_layer_field(ui: &RootUI, field: &str, value: f32) {
+    set_row_field(ui.get_layers(), field, |key, layer: &mut LayerItem| {
+        if key != "layer_gain" {
+            return false;
+        }
+        layer.gain = value.clamp(0.0, 2.0);
+        true
+    });
+}
+
+/// Start the sleep countdown over when it gets switched on or its length changes
+fn restart_sleep_if_changed(old: &Config, new: &Config, sleep: &SleepController) {
+    if new.sleep_timer && (!old.sleep_timer || old.sleep_minutes != new.sleep_minutes) {
+        sleep.restart(new.sleep_minutes);
+    }
+}
+
+/// Re-read the presets directory into the main screen list
+fn refresh_presets(ui: &RootUI, store: &PresetStore) {
+    let items: Vec<PresetItem> = store
+        .list()
+        .into_iter()
+        .map(|p| PresetItem {
+            name: p.name.into(),
+            builtin: p.builtin,
+        })
+        .collect();
+    ui.set_presets(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
 /// Snapshot all UI state into `cfg`, sanitize, and write config.toml
-fn flush_ui_to_config(ui: &RootUI, cfg: &mut Config) {
+fn flush_ui_to_config(ui: &RootUI, cfg: &mut Config, last_saved: &Mutex<String>) {
     // top-level
     cfg.volume = ui.get_volume();
-    cfg.alpha = ui.get_alpha();
+    cfg.fade_ms = ui.get_fade_ms();
+    if let Some(rate) = choice_value(&SAMPLE_RATE_CHOICES, ui.get_sample_rate()) {
+        cfg.sample_rate = rate;
+    }
+    if let Some(frames) = choice_value(&BUFFER_CHOICES, ui.get_buffer_frames()) {
+        cfg.buffer_frames = frames;
+    }
+    cfg.noise_color = NoiseColor::from_index(ui.get_noise_color());
+    cfg.brown_cutoff_hz = ui.get_brown_cutoff();
+    cfg.tilt_db_per_octave = ui.get_tilt();
+    cfg.stereo_width = ui.get_stereo_width();
+    cfg.balance = ui.get_balance();
     cfg.bass_boost = ui.get_bass_boost();
-    cfg.enable_low = ui.get_enable_low();
-    cfg.enable_mid = ui.get_enable_mid();
-    cfg.enable_high = ui.get_enable_high();
+    cfg.bass_boost_freq = ui.get_bass_boost_freq();
+    cfg.bass_boost_slope = ui.get_bass_boost_slope();
+    cfg.bass_boost_target = BoostTarget::from_index(ui.get_bass_boost_target());
     cfg.random = ui.get_random_seed();
 
     // Only take seed from UI when manual
@@ -71,40 +356,47 @@ fn flush_ui_to_config(ui: &RootUI, cfg: &mut Config) {
         cfg.seed = ui.get_seed().round().clamp(0.0, 65535.0) as u64;
     }
 
-    // per-band volumes
-    cfg.band_volume_low = ui.get_band_volume_low();
-    cfg.band_volume_mid = ui.get_band_volume_mid();
-    cfg.band_volume_high = ui.get_band_volume_high();
-
-    // band ranges
-    cfg.band_low = [ui.get_band_low_lo(), ui.get_band_low_hi()];
-    cfg.band_mid = [ui.get_band_mid_lo(), ui.get_band_mid_hi()];
-    cfg.band_high = [ui.get_band_high_lo(), ui.get_band_high_hi()];
+    // bands
+    cfg.bands = ui
+        .get_bands()
+        .iter()
+        .map(|item| item_to_band(&item))
+        .collect();
+    cfg.lfos = ui
+        .get_lfos()
+        .iter()
+        .map(|item| item_to_lfo(&item))
+        .collect();
+    cfg.layers = ui
+        .get_layers()
+        .iter()
+        .map(|item| item_to_layer(&item))
+        .collect();
+
+    // sleep timer
+    cfg.sleep_timer = ui.get_sleep_timer();
+    cfg.sleep_minutes = ui.get_sleep_minutes();
+    cfg.sleep_fade_minutes = ui.get_sleep_fade_minutes();
+    cfg.sleep_fade_curve = FadeCurve::from_index(ui.get_sleep_fade_curve());
+
+    // soundscape
+    cfg.soundscape = Soundscape::from_index(ui.get_soundscape());
+    cfg.soundscape_level = ui.get_soundscape_level();
+
+    // tone layer
+    cfg.tone = ui.get_tone();
+    cfg.tone_carrier = ui.get_tone_carrier();
+    cfg.tone_beat = ui.get_tone_beat();
+    cfg.tone_level = ui.get_tone_level();
+    cfg.tone_mode = ToneMode::from_index(ui.get_tone_mode());
+    cfg.tone_duty = ui.get_tone_duty();
+    cfg.tone_envelope = ToneEnvelope::from_index(ui.get_tone_envelope());
 
     // keep things sane, then persist
     cfg.sanitize();
-    if let Ok(toml) = toml::to_string_pretty(&*cfg) {
-        let _ = std::fs::write(config_path(), toml);
-    }
-
-    ui.set_volume(cfg.volume);
-    ui.set_alpha(cfg.alpha);
-    ui.set_bass_boost(cfg.bass_boost);
-
-    ui.set_enable_low(cfg.enable_low);
-    ui.set_enable_mid(cfg.enable_mid);
-    ui.set_enable_high(cfg.enable_high);
+    save_config(&config_path(), cfg, last_saved);
 
-    ui.set_band_volume_low(cfg.band_volume_low);
-    ui.set_band_volume_mid(cfg.band_volume_mid);
-    ui.set_band_volume_high(cfg.band_volume_high);
-
-    ui.set_band_low_lo(cfg.band_low[0]);
-    ui.set_band_low_hi(cfg.band_low[1]);
-    ui.set_band_mid_lo(cfg.band_mid[0]);
-    ui.set_band_mid_hi(cfg.band_mid[1]);
-    ui.set_band_high_lo(cfg.band_high[0]);
-    ui.set_band_high_hi(cfg.band_high[1]);
+    push_config_to_ui(ui, cfg);
 }
 
 // Shared app logic — used by both desktop and Android
@@ -112,11 +404,16 @@ pub fn run_app() -> Result<()> {
     let path = config_path();
     let mut config = load_or_create_config(&path)?;
     config.sanitize();
-    let shared_cfg = Arc::new(Mutex::new(config));
+    let (shared_cfg, cfg_rx) = SharedConfig::new(config);
+
+    // Last text we wrote (or loaded), so the file watcher ignores our own saves
+    let last_saved = Arc::new(Mutex::new(
+        std::fs::read_to_string(&path).unwrap_or_default(),
+    ));
 
     // Random or fixed seed
     let seed_value = {
-        let cfg = shared_cfg.lock().unwrap();
+        let cfg = shared_cfg.lock();
         if cfg.random {
             let s: u16 = rand::rng().random();
             println!("Random seed: {s}");
@@ -127,12 +424,11 @@ pub fn run_app() -> Result<()> {
     };
 
     let runtime_seed = Arc::new(Mutex::new(seed_value));
-    let mut rng_seeded = SmallRng::seed_from_u64(seed_value);
 
     // Start audio
-    let (audio_device, mute_ctrl) =
-        init_stream(shared_cfg.clone(), &mut rng_seeded).context("Failed to start audio output")?;
-    std::mem::forget(audio_device);
+    let (audio_device, mute_ctrl, sleep_ctrl, sample_loader, stats_ctrl) =
+        init_stream(cfg_rx, seed_value).context("Failed to start audio output")?;
+    shared_cfg.attach_loader(sample_loader);
 
     println!("Noise generator running – edit config.toml or use UI");
 
@@ -145,52 +441,59 @@ pub fn run_app() -> Result<()> {
     let ui = RootUI::new().unwrap();
 
     ui.window().set_size(slint::LogicalSize::new(460.0, 720.0));
+    ui.set_max_bands(MAX_BANDS as i32);
+    ui.set_max_lfos(MAX_LFOS as i32);
+    ui.set_max_layers(MAX_LAYERS as i32);
 
     // Load config into UI
     {
-        let cfg = shared_cfg.lock().unwrap();
-        ui.set_volume(cfg.volume);
-        ui.set_alpha(cfg.alpha);
-        ui.set_bass_boost(cfg.bass_boost);
-
-        ui.set_enable_low(cfg.enable_low);
-        ui.set_enable_mid(cfg.enable_mid);
-        ui.set_enable_high(cfg.enable_high);
-        ui.set_random_seed(cfg.random);
-
-        ui.set_band_volume_low(cfg.band_volume_low);
-        ui.set_band_volume_mid(cfg.band_volume_mid);
-        ui.set_band_volume_high(cfg.band_volume_high);
-
-        ui.set_band_low_lo(cfg.band_low[0]);
-        ui.set_band_low_hi(cfg.band_low[1]);
-        ui.set_band_mid_lo(cfg.band_mid[0]);
-        ui.set_band_mid_hi(cfg.band_mid[1]);
-        ui.set_band_high_lo(cfg.band_high[0]);
-        ui.set_band_high_hi(cfg.band_high[1]);
-
+        let cfg = shared_cfg.lock();
+        push_config_to_ui(&ui, &cfg);
         ui.set_seed(seed_value as f32);
+        // Nothing has edited the config since the stream opened with it
+        ui.set_running_rate(cfg.output_sample_rate() as i32);
+        ui.set_running_buffer(cfg.buffer_frames as i32);
     }
 
+    // Random-seed toggle state as last seen by the UI
+    let prev_random = Arc::new(Mutex::new(shared_cfg.lock().random));
+
+    // Hot-reload external edits to config.toml into the audio thread and the UI
+    let _config_watcher = {
+        let sc = shared_cfg.clone();
+        let ui_weak = ui.as_weak();
+        let prev_random = prev_random.clone();
+        let sleep = sleep_ctrl.clone();
+        watch_config(path.clone(), last_saved.clone(), move |new_cfg| {
+            let mut cfg = sc.lock();
+            restart_sleep_if_changed(&cfg, &new_cfg, &sleep);
+            *cfg = new_cfg.clone();
+            drop(cfg);
+            *prev_random.lock().unwrap() = new_cfg.random;
+            let _ = ui_weak.upgrade_in_event_loop(move |ui| push_config_to_ui(&ui, &new_cfg));
+        })
+        .map_err(|e| eprintln!("warning: config hot-reload unavailable: {e}"))
+        .ok()
+    };
+
     // CENTRALIZED CONFIG WRITE
     {
         let sc = shared_cfg.clone();
         let ui_weak_cfg = ui.as_weak();
         let ui_weak_num = ui.as_weak();
 
-        let prev_random = Arc::new(Mutex::new({
-            let cfg = sc.lock().unwrap();
-            cfg.random
-        }));
+        let prev_random = prev_random.clone();
         let started_random = Arc::new(Mutex::new({
-            let cfg = sc.lock().unwrap();
+            let cfg = sc.lock();
             cfg.random
         }));
         let runtime_seed = runtime_seed.clone();
+        let last_saved = last_saved.clone();
+        let sleep = sleep_ctrl.clone();
 
         ui.on_config_changed(move || {
             if let Some(ui) = ui_weak_cfg.upgrade() {
-                let mut cfg = sc.lock().unwrap();
+                let mut cfg = sc.lock();
 
                 let was_random = *prev_random.lock().unwrap();
                 let now_random = ui.get_random_seed();
@@ -212,7 +515,9 @@ pub fn run_app() -> Result<()> {
 
                 *prev_random.lock().unwrap() = now_random;
 
-                flush_ui_to_config(&ui, &mut cfg);
+                let before = cfg.clone();
+                flush_ui_to_config(&ui, &mut cfg, &last_saved);
+                restart_sleep_if_changed(&before, &cfg, &sleep);
             }
         });
 
@@ -245,22 +550,35 @@ pub fn run_app() -> Result<()> {
 
                 match (field, v) {
                     ("volume_pct", Some(pct)) => ui.set_volume((pct / 100.0).clamp(0.0, 1.0)),
-                    ("alpha", Some(a)) => ui.set_alpha(a.clamp(0.9, 0.9999)),
+                    ("fade_ms", Some(ms)) => ui.set_fade_ms(ms.round().clamp(0.0, 2000.0)),
+                    ("brown_cutoff", Some(hz)) => ui.set_brown_cutoff(hz.clamp(0.5, 800.0)),
+                    ("tilt", Some(t)) => ui.set_tilt(t.clamp(-6.0, 6.0)),
+                    ("stereo_width", Some(w)) => ui.set_stereo_width(w.clamp(0.0, 2.0)),
+                    ("balance", Some(b)) => ui.set_balance(b.clamp(-1.0, 1.0)),
 
-                    ("band_low_lo", Some(x)) => ui.set_band_low_lo(x.clamp(1.0, 1000.0)),
-                    ("band_low_hi", Some(x)) => ui.set_band_low_hi(x.clamp(1.0, 1000.0)),
-                    ("band_mid_lo", Some(x)) => ui.set_band_mid_lo(x.clamp(1.0, 5000.0)),
-                    ("band_mid_hi", Some(x)) => ui.set_band_mid_hi(x.clamp(1.0, 5000.0)),
-                    ("band_high_lo", Some(x)) => ui.set_band_high_lo(x.clamp(1.0, 10000.0)),
-                    ("band_high_hi", Some(x)) => ui.set_band_high_hi(x.clamp(1.0, 10000.0)),
-
-                    ("band_volume_low", Some(x)) => ui.set_band_volume_low(x.clamp(0.0, 10.0)),
-                    ("band_volume_mid", Some(x)) => ui.set_band_volume_mid(x.clamp(0.0, 10.0)),
-                    ("band_volume_high", Some(x)) => ui.set_band_volume_high(x.clamp(0.0, 10.0)),
+                    (f, Some(x)) if f.starts_with("band_") => set_band_field(&ui, f, x),
+                    (f, Some(x)) if f.starts_with("lfo_") => set_lfo_field(&ui, f, x),
+                    (f, Some(x)) if f.starts_with("layer_") => set_layer_field(&ui, f, x),
 
                     ("bass_boost", Some(x)) => ui.set_bass_boost(x.clamp(0.0, 10.0)),
+                    ("bass_boost_freq", Some(x)) => {
+                        ui.set_bass_boost_freq(x.round().clamp(20.0, 500.0))
+                    }
+                    ("bass_boost_slope", Some(x)) => ui.set_bass_boost_slope(x.clamp(0.1, 1.0)),
 
                     ("seed", Some(x)) => ui.set_seed(x.round().clamp(0.0, 65535.0)),
+
+                    ("sleep_minutes", Some(x)) => {
+                        ui.set_sleep_minutes(x.round().clamp(15.0, 480.0))
+                    }
+                    ("sleep_fade_minutes", Some(x)) => {
+                        ui.set_sleep_fade_minutes(x.round().clamp(0.0, 60.0))
+                    }
+                    ("tone_carrier", Some(x)) => ui.set_tone_carrier(x.round().clamp(40.0, 1000.0)),
+                    ("tone_beat", Some(x)) => ui.set_tone_beat(x.clamp(0.5, 40.0)),
+                    ("soundscape_level", Some(x)) => ui.set_soundscape_level(x.clamp(0.0, 1.0)),
+                    ("tone_level", Some(x)) => ui.set_tone_level(x.clamp(0.0, 1.0)),
+                    ("tone_duty", Some(x)) => ui.set_tone_duty(x.clamp(0.1, 0.9)),
                     _ => { /* no-op on parse error / unknown field */ }
                 }
 
@@ -269,6 +587,111 @@ pub fn run_app() -> Result<()> {
         });
     }
 
+    // Band list
+    {
+        let ui_weak = ui.as_weak();
+        ui.on_add_band(move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let mut bands: Vec<BandItem> = ui.get_bands().iter().collect();
+            if bands.len() >= MAX_BANDS {
+                return;
+            }
+            // Start just above the last band
+            let lo = bands.last().map_or(100.0, |b| b.hi).min(10000.0);
+            let band = Band::new(
+                &format!("Band {}", bands.len() + 1),
+                [lo, (lo * 4.0).min(20000.0)],
+                0.5,
+            );
+            bands.push(band_to_item(&band));
+            ui.set_bands(slint::ModelRc::new(slint::VecModel::from(bands)));
+            refresh_band_names(&ui);
+            ui.invoke_config_changed();
+        });
+    }
+    {
+        let ui_weak = ui.as_weak();
+        ui.on_remove_band(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let mut bands: Vec<BandItem> = ui.get_bands().iter().collect();
+            if index < 0 || index as usize >= bands.len() {
+                return;
+            }
+            bands.remove(index as usize);
+            ui.set_bands(slint::ModelRc::new(slint::VecModel::from(bands)));
+            refresh_band_names(&ui);
+
+            // Keep LFOs pointing at the same bands; ones on the removed band fall back to the first
+            let lfos = ui.get_lfos();
+            for i in 0..lfos.row_count() {
+                let Some(mut lfo) = lfos.row_data(i) else {
+                    continue;
+                };
+                if lfo.band >= index {
+                    lfo.band = if lfo.band == index { 0 } else { lfo.band - 1 };
+                    lfos.set_row_data(i, lfo);
+                }
+            }
+            ui.invoke_config_changed();
+        });
+    }
+
+    // LFO list
+    {
+        let ui_weak = ui.as_weak();
+        ui.on_add_lfo(move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let mut lfos: Vec<LfoItem> = ui.get_lfos().iter().collect();
+            if lfos.len() >= MAX_LFOS {
+                return;
+            }
+            lfos.push(lfo_to_item(&Lfo::default()));
+            ui.set_lfos(slint::ModelRc::new(slint::VecModel::from(lfos)));
+            ui.invoke_config_changed();
+        });
+    }
+    {
+        let ui_weak = ui.as_weak();
+        ui.on_remove_lfo(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let mut lfos: Vec<LfoItem> = ui.get_lfos().iter().collect();
+            if index < 0 || index as usize >= lfos.len() {
+                return;
+            }
+            lfos.remove(index as usize);
+            ui.set_lfos(slint::ModelRc::new(slint::VecModel::from(lfos)));
+            ui.invoke_config_changed();
+        });
+    }
+
+    // Sample layer list
+    {
+        let ui_weak = ui.as_weak();
+        ui.on_add_layer(move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let mut layers: Vec<LayerItem> = ui.get_layers().iter().collect();
+            if layers.len() >= MAX_LAYERS {
+                return;
+            }
+            layers.push(layer_to_item(&SampleLayer::default()));
+            ui.set_layers(slint::ModelRc::new(slint::VecModel::from(layers)));
+            ui.invoke_config_changed();
+        });
+    }
+    {
+        let ui_weak = ui.as_weak();
+        ui.on_remove_layer(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let mut layers: Vec<LayerItem> = ui.get_layers().iter().collect();
+            if index < 0 || index as usize >= layers.len() {
+                return;
+            }
+            layers.remove(index as usize);
+            ui.set_layers(slint::ModelRc::new(slint::VecModel::from(layers)));
+            ui.invoke_config_changed();
+        });
+    }
+
     // Volume text entry
     {
         let ui_weak = ui.as_weak();
@@ -287,13 +710,106 @@ pub fn run_app() -> Result<()> {
         });
     }
 
+    // Presets
+    {
+        let store = Rc::new(PresetStore::new(&path));
+        refresh_presets(&ui, &store);
+
+        {
+            let store = store.clone();
+            let sc = shared_cfg.clone();
+            let prev_random = prev_random.clone();
+            let last_saved = last_saved.clone();
+            let path = path.clone();
+            let sleep = sleep_ctrl.clone();
+            let ui_weak = ui.as_weak();
+            ui.on_preset_selected(move |name, builtin| {
+                let Some(ui) = ui_weak.upgrade() else { return };
+                match store.load(&name, builtin) {
+                    Ok(preset) => {
+                        let mut cfg = sc.lock();
+                        restart_sleep_if_changed(&cfg, &preset, &sleep);
+                        // Output rate and buffer size belong to this machine, not the sound
+                        let (sample_rate, buffer_frames) = (cfg.sample_rate, cfg.buffer_frames);
+                        *cfg = preset;
+                        cfg.sample_rate = sample_rate;
+                        cfg.buffer_frames = buffer_frames;
+                        *prev_random.lock().unwrap() = cfg.random;
+                        push_config_to_ui(&ui, &cfg);
+                        save_config(&path, &cfg, &last_saved);
+                        ui.set_preset_status("".into());
+                    }
+                    Err(e) => ui.set_preset_status(e.to_string().into()),
+                }
+            });
+        }
+
+        {
+            let store = store.clone();
+            let sc = shared_cfg.clone();
+            let ui_weak = ui.as_weak();
+            ui.on_preset_save(move |name| {
+                let Some(ui) = ui_weak.upgrade() else { return };
+                let name = name.trim().to_string();
+                let result = store.save(&name, &sc.lock());
+                match result {
+                    Ok(()) => {
+                        refresh_presets(&ui, &store);
+                        ui.set_current_preset(name.as_str().into());
+                        ui.set_current_preset_builtin(false);
+                        ui.set_preset_status(format!("Saved '{name}'").into());
+                    }
+                    Err(e) => ui.set_preset_status(e.to_string().into()),
+                }
+            });
+        }
+
+        {
+            let store = store.clone();
+            let ui_weak = ui.as_weak();
+            ui.on_preset_rename(move |from, to| {
+                let Some(ui) = ui_weak.upgrade() else { return };
+                let to = to.trim().to_string();
+                match store.rename(&from, &to) {
+                    Ok(()) => {
+                        refresh_presets(&ui, &store);
+                        ui.set_current_preset(to.as_str().into());
+                        ui.set_preset_status(format!("Renamed '{from}' to '{to}'").into());
+                    }
+                    Err(e) => ui.set_preset_status(e.to_string().into()),
+                }
+            });
+        }
+
+        {
+            let ui_weak = ui.as_weak();
+            ui.on_preset_delete(move |name| {
+                let Some(ui) = ui_weak.upgrade() else { return };
+                match store.delete(&name) {
+                    Ok(()) => {
+                        refresh_presets(&ui, &store);
+                        ui.set_current_preset("".into());
+                        ui.set_preset_status(format!("Deleted '{name}'").into());
+                    }
+                    Err(e) => ui.set_preset_status(e.to_string().into()),
+                }
+            });
+        }
+    }
+
     // Play/pause
     ui.set_internal_playing(!mute_ctrl.is_muted());
     {
         let mc = mute_ctrl.clone();
+        let sc = shared_cfg.clone();
+        let sleep = sleep_ctrl.clone();
         let ui_weak = ui.as_weak();
         ui.on_toggle_play(move || {
             let now_muted = mc.toggle();
+            if !now_muted {
+                let minutes = sc.lock().sleep_minutes;
+                sleep.restart(minutes);
+            }
             if let Some(ui) = ui_weak.upgrade() {
                 ui.set_internal_playing(!now_muted);
             }
@@ -301,6 +817,55 @@ pub fn run_app() -> Result<()> {
         });
     }
 
+    // Diagnostics reset
+    {
+        let stats = stats_ctrl.clone();
+        ui.on_reset_stats(move || stats.reset());
+    }
+
+    // Sleep countdown + auto-pause (the audio thread mutes when the timer runs out),
+    // and the callback timing readout
+    let status_timer = slint::Timer::default();
+    {
+        let mc = mute_ctrl.clone();
+        let sleep = sleep_ctrl.clone();
+        let stats = stats_ctrl.clone();
+        let ui_weak = ui.as_weak();
+        status_timer.start(
+            slint::TimerMode::Repeated,
+            Duration::from_millis(500),
+            move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_internal_playing(!mc.is_muted());
+                    let text = match sleep.remaining_secs() {
+                        Some(s) => {
+                            format!("Sleep in {}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+                        }
+                        None => String::new(),
+                    };
+                    ui.set_sleep_remaining(text.into());
+
+                    let st = stats.snapshot();
+                    let load = if st.buffer_ms > 0.0 {
+                        st.avg_ms / st.buffer_ms * 100.0
+                    } else {
+                        0.0
+                    };
+                    ui.set_timing_text(
+                        format!(
+                            "Callback {:.2} ms avg, {:.2} ms peak of {:.1} ms ({load:.0}% load)",
+                            st.avg_ms, st.peak_ms, st.buffer_ms
+                        )
+                        .into(),
+                    );
+                    ui.set_late_text(
+                        format!("Late callbacks: {} of {}", st.late, st.callbacks).into(),
+                    );
+                }
+            },
+        );
+    }
+
     // Close
     ui.window().on_close_requested(|| {
         let _ = slint::quit_event_loop();
@@ -309,6 +874,11 @@ pub fn run_app() -> Result<()> {
 
     ui.run().unwrap();
     println!("UI exited, shutting down...");
+
+    // Fade out before the device goes away so quitting doesn't click
+    let fade_ms = shared_cfg.lock().fade_ms;
+    mute_ctrl.fade_out(Duration::from_millis(fade_ms as u64 + 250));
+    drop(audio_device);
     Ok(())
 }
 
@@ -1,9 +1,12 @@
-// Dummy wrapper for Desktop
+// Desktop wrapper: `render` subcommand for offline WAV output, otherwise the UI
 #[cfg(not(target_os = "android"))]
 fn main() -> anyhow::Result<()> {
-    noise_generator::run_app()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("render") => noise_generator::run_render(&args[1..]),
+        _ => noise_generator::run_app(),
+    }
 }
 
 #[cfg(target_os = "android")]
-fn main() {
-}
+fn main() {}
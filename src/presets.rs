@@ -0,0 +1,120 @@
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+
+use crate::config::{BUILTIN_PRESETS, Config};
+
+pub struct PresetEntry {
+    pub name: String,
+    pub builtin: bool,
+}
+
+/// Factory presets plus named configs stored as `<name>.toml` in a `presets`
+/// directory next to config.toml. Built-in names are reserved.
+pub struct PresetStore {
+    dir: PathBuf,
+}
+
+impl PresetStore {
+    pub fn new(config_path: &Path) -> Self {
+        let dir = config_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("presets");
+        let _ = std::fs::create_dir_all(&dir);
+        Self { dir }
+    }
+
+    /// Built-ins first, then user presets sorted case-insensitively
+    pub fn list(&self) -> Vec<PresetEntry> {
+        let mut names: Vec<String> = std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+                    .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort_by_key(|n| n.to_lowercase());
+
+        BUILTIN_PRESETS
+            .iter()
+            .map(|n| PresetEntry {
+                name: n.to_string(),
+                builtin: true,
+            })
+            .chain(names.into_iter().map(|name| PresetEntry {
+                name,
+                builtin: false,
+            }))
+            .collect()
+    }
+
+    pub fn load(&self, name: &str, builtin: bool) -> Result<Config> {
+        if builtin {
+            return Config::builtin(name).with_context(|| format!("No built-in preset '{name}'"));
+        }
+        let path = self.path_for(name)?;
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Preset '{name}' not found"))?;
+        let mut cfg =
+            Config::from_toml(&content).with_context(|| format!("Preset '{name}' is invalid"))?;
+        cfg.sanitize();
+        Ok(cfg)
+    }
+
+    /// Built-in if the name is reserved, otherwise the user's file
+    pub fn load_any(&self, name: &str) -> Result<Config> {
+        self.load(name, Config::is_builtin(name))
+    }
+
+    /// Create or overwrite a preset
+    pub fn save(&self, name: &str, cfg: &Config) -> Result<()> {
+        Self::check_not_builtin(name)?;
+        let path = self.path_for(name)?;
+        let toml = toml::to_string_pretty(cfg)?;
+        std::fs::write(&path, toml).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<()> {
+        Self::check_not_builtin(to)?;
+        let src = self.path_for(from)?;
+        let dst = self.path_for(to)?;
+        if src == dst {
+            return Ok(());
+        }
+        if dst.exists() {
+            bail!("A preset named '{}' already exists", to.trim());
+        }
+        std::fs::rename(&src, &dst).with_context(|| format!("Failed to rename '{from}'"))
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let path = self.path_for(name)?;
+        std::fs::remove_file(&path).with_context(|| format!("Failed to delete '{name}'"))
+    }
+
+    fn check_not_builtin(name: &str) -> Result<()> {
+        if Config::is_builtin(name) {
+            bail!("'{}' is a built-in preset; pick another name", name.trim());
+        }
+        Ok(())
+    }
+
+    /// Validate a user-typed name and map it to a file inside the presets dir
+    fn path_for(&self, name: &str) -> Result<PathBuf> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("Preset name is empty");
+        }
+        if name.starts_with('.')
+            || name.chars().any(|c| {
+                matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+            })
+        {
+            bail!("Preset names can't contain / \\ : * ? \" < > | or start with '.'");
+        }
+        Ok(self.dir.join(format!("{name}.toml")))
+    }
+}
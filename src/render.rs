@@ -0,0 +1,203 @@
+use anyhow::{Context, Result, bail};
+use rand::Rng;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, MAX_SAMPLE_RATE, MIN_SAMPLE_RATE, load_or_create_config};
+use crate::engine::{CHANNELS, NoiseEngine};
+use crate::presets::PresetStore;
+use crate::sample::load_layers;
+use crate::wav::WavWriter;
+
+const USAGE: &str = "\
+Usage: noise-generator render --out <file.wav> [options]
+
+Options:
+  -o, --out <path>        Output WAV file (required)
+  -d, --duration <time>   Length, e.g. 30s, 15m, 8h, 1h30m (default: 60s)
+  -r, --rate <hz>         Sample rate (default: the config's, or 48000)
+  -c, --config <path>     Config to render (default: the app's config.toml)
+  -p, --preset <name>     Render a built-in or saved preset instead of a config file
+  -s, --seed <n>          Seed (default: config seed, or a printed random one)";
+
+// Frames rendered per engine call
+const CHUNK_FRAMES: usize = 4096;
+
+/// Everything needed to render a file, independent of where it came from
+pub struct RenderJob {
+    pub config: Config,
+    pub seconds: f64,
+    pub sample_rate: u32,
+    pub seed: u64,
+    pub out: PathBuf,
+}
+
+/// Parse `30s`, `15m`, `8h`, `1h30m`, or bare seconds
+fn parse_duration(raw: &str) -> Option<f64> {
+    let t = raw.trim().to_lowercase();
+    if let Ok(secs) = t.parse::<f64>() {
+        return Some(secs);
+    }
+
+    let mut total = 0.0;
+    let mut num = String::new();
+    for c in t.chars() {
+        match c {
+            '0'..='9' | '.' => num.push(c),
+            'h' | 'm' | 's' => {
+                let v: f64 = num.parse().ok()?;
+                total += v * match c {
+                    'h' => 3600.0,
+                    'm' => 60.0,
+                    _ => 1.0,
+                };
+                num.clear();
+            }
+            _ => return None,
+        }
+    }
+    if !num.is_empty() {
+        return None;
+    }
+    Some(total)
+}
+
+impl RenderJob {
+    /// Build a job from the arguments following `render`
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut out = None;
+        let mut seconds = 60.0;
+        let mut sample_rate = None;
+        let mut config_file = None;
+        let mut preset = None;
+        let mut seed = None;
+
+        let mut it = args.iter();
+        while let Some(flag) = it.next() {
+            if flag == "-h" || flag == "--help" {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            let value = it
+                .next()
+                .with_context(|| format!("missing value for {flag}\n\n{USAGE}"))?;
+            match flag.as_str() {
+                "-o" | "--out" => out = Some(PathBuf::from(value)),
+                "-d" | "--duration" => {
+                    seconds = parse_duration(value)
+                        .with_context(|| format!("invalid duration '{value}'"))?;
+                }
+                "-r" | "--rate" => {
+                    sample_rate = Some(
+                        value
+                            .parse::<u32>()
+                            .with_context(|| format!("invalid sample rate '{value}'"))?,
+                    );
+                }
+                "-c" | "--config" => config_file = Some(PathBuf::from(value)),
+                "-p" | "--preset" => preset = Some(value.clone()),
+                "-s" | "--seed" => {
+                    seed = Some(
+                        value
+                            .parse::<u64>()
+                            .with_context(|| format!("invalid seed '{value}'"))?
+                            .min(65535),
+                    );
+                }
+                _ => bail!("unknown option '{flag}'\n\n{USAGE}"),
+            }
+        }
+
+        let Some(out) = out else {
+            bail!("--out is required\n\n{USAGE}");
+        };
+        if !seconds.is_finite() || seconds <= 0.0 {
+            bail!("duration must be greater than zero");
+        }
+        let config = match (config_file, preset) {
+            (Some(_), Some(_)) => bail!("use either --config or --preset, not both"),
+            (Some(path), None) => load_config_file(&path)?,
+            (None, Some(name)) => PresetStore::new(&crate::config_path()).load_any(&name)?,
+            (None, None) => load_or_create_config(crate::config_path())?,
+        };
+
+        let sample_rate = sample_rate.unwrap_or(config.output_sample_rate());
+        if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) {
+            bail!("sample rate must be between {MIN_SAMPLE_RATE} and {MAX_SAMPLE_RATE} Hz");
+        }
+
+        // Same rules as the app: explicit seed wins, then a fixed config seed
+        let seed = seed.unwrap_or_else(|| {
+            if config.random {
+                let s: u16 = rand::rng().random();
+                eprintln!("Random seed: {s} (pass --seed {s} to reproduce)");
+                s as u64
+            } else {
+                config.seed
+            }
+        });
+
+        Ok(Self {
+            config,
+            seconds,
+            sample_rate,
+            seed,
+            out,
+        })
+    }
+
+    /// Render the whole file, reporting progress as a 0.0–1.0 fraction
+    pub fn run(&self, mut progress: impl FnMut(f64)) -> Result<()> {
+        let total_frames = (self.seconds * self.sample_rate as f64).round() as u64;
+        let mut engine = NoiseEngine::new(&self.config, self.sample_rate as f32, self.seed);
+        engine.set_samples(&load_layers(&self.config, self.sample_rate as f32)?);
+        let mut wav = WavWriter::create(&self.out, self.sample_rate, CHANNELS as u16, total_frames)
+            .with_context(|| format!("Failed to create {}", self.out.display()))?;
+
+        let mut buffer = vec![0.0f32; CHUNK_FRAMES * CHANNELS];
+        let mut done = 0u64;
+        while done < total_frames {
+            let frames = (total_frames - done).min(CHUNK_FRAMES as u64) as usize;
+            let chunk = &mut buffer[..frames * CHANNELS];
+            engine.render(chunk);
+            wav.write_samples(chunk)?;
+            done += frames as u64;
+            progress(done as f64 / total_frames as f64);
+        }
+
+        wav.finish()
+    }
+}
+
+fn load_config_file(path: &Path) -> Result<Config> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut cfg = Config::from_toml(&content)?;
+    cfg.sanitize();
+    Ok(cfg)
+}
+
+/// `noise-generator render ...` entry point
+pub fn run_render(args: &[String]) -> Result<()> {
+    let job = RenderJob::from_args(args)?;
+    eprintln!(
+        "Rendering {:.0}s at {} Hz (seed {}) to {}",
+        job.seconds,
+        job.sample_rate,
+        job.seed,
+        job.out.display()
+    );
+
+    let mut last_pct = u32::MAX;
+    job.run(|fraction| {
+        let pct = (fraction * 100.0) as u32;
+        if pct != last_pct {
+            last_pct = pct;
+            eprint!("\r{pct:3}%");
+            let _ = std::io::stderr().flush();
+        }
+    })?;
+
+    eprintln!("\rDone: {}", job.out.display());
+    Ok(())
+}
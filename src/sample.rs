@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+use std::path::Path;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+
+use crate::biquad::Biquad;
+use crate::config::{Config, MAX_LAYERS};
+use crate::smooth::FadeSwap;
+use crate::triple_buffer::{Reader, triple_buffer};
+use crate::wav::{Pcm, read_wav};
+
+// End of a loop is blended into its start over this long, capped at a quarter of the file
+const LOOP_CROSSFADE_SECS: f32 = 0.5;
+// Decoded stereo f32 takes ~23 MB per minute at 48 kHz
+const MAX_LAYER_SECS: f32 = 600.0;
+// Layer volume glides; swapping files fades out, swaps, fades in
+const LEVEL_RAMP_SECS: f32 = 0.1;
+// How often the loader drops loops nothing plays anymore
+const PRUNE_INTERVAL: Duration = Duration::from_secs(2);
+// Anti-alias low-pass before downsampling: an 8th-order Butterworth (as four
+// biquads) with its corner at this fraction of the new rate
+const ANTI_ALIAS_CORNER: f32 = 0.45;
+const ANTI_ALIAS_Q: [f32; 4] = [0.5098, 0.6013, 0.9000, 2.5629];
+// Frames from the other end of the loop run through first, so the filter
+// state at the seam matches what playback wraps into
+const ANTI_ALIAS_WARMUP: usize = 4096;
+
+// -------------------------
+// Decoding
+// -------------------------
+
+/// A loop decoded to stereo at the engine rate, with the seam crossfade baked
+/// in so playback can simply wrap around
+pub struct SampleBuffer {
+    frames: Vec<[f32; 2]>,
+}
+
+/// One optional loop per layer slot, as handed to [`crate::NoiseEngine::set_samples`]
+pub type LayerBuffers = [Option<Arc<SampleBuffer>>; MAX_LAYERS];
+
+impl SampleBuffer {
+    /// Decode a WAV or FLAC file (picked by extension) and prepare it for `sample_rate`
+    pub fn load(path: &Path, sample_rate: f32) -> Result<Self> {
+        let pcm = decode(path)?;
+        if pcm.channels == 0 || pcm.sample_rate == 0 {
+            bail!("no audio in file");
+        }
+        let secs = pcm.samples.len() as f32 / pcm.channels as f32 / pcm.sample_rate as f32;
+        if secs > MAX_LAYER_SECS {
+            bail!("file is {secs:.0} s long, layers are limited to {MAX_LAYER_SECS:.0} s");
+        }
+
+        let frames = resample(to_stereo(&pcm), pcm.sample_rate as f32, sample_rate);
+        if frames.len() < 4 {
+            bail!("file is too short to loop");
+        }
+        let fade = (LOOP_CROSSFADE_SECS * sample_rate) as usize;
+        Ok(Self {
+            frames: crossfade_loop(frames, fade),
+        })
+    }
+}
+
+fn decode(path: &Path) -> Result<Pcm> {
+    let is_flac = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("flac"));
+    if is_flac {
+        decode_flac(path)
+    } else {
+        read_wav(path)
+    }
+}
+
+fn decode_flac(path: &Path) -> Result<Pcm> {
+    let mut reader = claxon::FlacReader::open(path).context("not a FLAC file")?;
+    let info = reader.streaminfo();
+    let scale = 1.0 / (1u64 << (info.bits_per_sample - 1)) as f32;
+    let samples = reader
+        .samples()
+        .map(|s| s.map(|v| v as f32 * scale))
+        .collect::<Result<Vec<_>, _>>()
+        .context("corrupt FLAC stream")?;
+    Ok(Pcm {
+        samples,
+        channels: info.channels as u16,
+        sample_rate: info.sample_rate,
+    })
+}
+
+/// Mono is doubled; anything past the first two channels is dropped
+fn to_stereo(pcm: &Pcm) -> Vec<[f32; 2]> {
+    pcm.samples
+        .chunks_exact(pcm.channels as usize)
+        .map(|c| {
+            if c.len() == 1 {
+                [c[0], c[0]]
+            } else {
+                [c[0], c[1]]
+            }
+        })
+        .collect()
+}
+
+/// Low-pass `frames` below the Nyquist of the lower rate `to`, forwards then
+/// backwards: zero phase, and twice the attenuation of a single pass
+fn anti_alias(frames: &mut [[f32; 2]], from: f32, to: f32) {
+    let warmup = ANTI_ALIAS_WARMUP.min(frames.len());
+    let new_filters = || -> [[Biquad; 4]; 2] {
+        std::array::from_fn(|_| {
+            ANTI_ALIAS_Q.map(|q| {
+                let mut f = Biquad::new();
+                f.update_lowpass(from, ANTI_ALIAS_CORNER * to, q);
+                f
+            })
+        })
+    };
+    let run = |filters: &mut [[Biquad; 4]; 2], frame: [f32; 2]| -> [f32; 2] {
+        std::array::from_fn(|ch| filters[ch].iter_mut().fold(frame[ch], |x, f| f.process(x)))
+    };
+
+    // Forwards, warmed up on the tail that precedes the start when looping
+    let mut filters = new_filters();
+    let len = frames.len();
+    for &frame in &frames[len - warmup..] {
+        run(&mut filters, frame);
+    }
+    for frame in frames.iter_mut() {
+        *frame = run(&mut filters, *frame);
+    }
+
+    // Backwards, warmed up on the head that follows the end
+    let mut filters = new_filters();
+    for &frame in frames[..warmup].iter().rev() {
+        run(&mut filters, frame);
+    }
+    for frame in frames.iter_mut().rev() {
+        *frame = run(&mut filters, *frame);
+    }
+}
+
+/// 4-point Hermite resampling, low-passed first when going down in rate so
+/// content above the new Nyquist doesn't fold back into the audible band.
+/// Neighbours wrap around since the result is looped.
+fn resample(mut frames: Vec<[f32; 2]>, from: f32, to: f32) -> Vec<[f32; 2]> {
+    if from == to || frames.is_empty() {
+        return frames;
+    }
+    if from > to {
+        anti_alias(&mut frames, from, to);
+    }
+    let len = frames.len();
+    let ratio = from as f64 / to as f64;
+    let out_len = (len as f64 / ratio).floor() as usize;
+    let at = |i: isize| frames[i.rem_euclid(len as isize) as usize];
+
+    (0..out_len)
+        .map(|n| {
+            let pos = n as f64 * ratio;
+            let i = pos.floor() as isize;
+            let t = (pos - i as f64) as f32;
+            let (xm1, x0, x1, x2) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+            std::array::from_fn(|ch| {
+                let c1 = 0.5 * (x1[ch] - xm1[ch]);
+                let c2 = xm1[ch] - 2.5 * x0[ch] + 2.0 * x1[ch] - 0.5 * x2[ch];
+                let c3 = 0.5 * (x2[ch] - xm1[ch]) + 1.5 * (x0[ch] - x1[ch]);
+                ((c3 * t + c2) * t + c1) * t + x0[ch]
+            })
+        })
+        .collect()
+}
+
+/// Fold the last `fade` frames over the first ones with an equal-power
+/// crossfade, so the end runs straight into the start without a click
+fn crossfade_loop(mut frames: Vec<[f32; 2]>, fade: usize) -> Vec<[f32; 2]> {
+    let fade = fade.min(frames.len() / 4);
+    let len = frames.len() - fade;
+    for i in 0..fade {
+        let t = (i as f32 + 0.5) / fade as f32 * FRAC_PI_2;
+        let (fade_in, fade_out) = (t.sin(), t.cos());
+        let tail = frames[len + i];
+        for (s, tail) in frames[i].iter_mut().zip(tail) {
+            *s = *s * fade_in + tail * fade_out;
+        }
+    }
+    frames.truncate(len);
+    frames
+}
+
+// -------------------------
+// Loader thread
+// -------------------------
+
+/// Decodes layer files off the audio thread. Each loop stays cached here for
+/// as long as anything else holds it, so the audio thread never frees one.
+pub struct SampleLoader {
+    tx: mpsc::Sender<Vec<String>>,
+}
+
+impl SampleLoader {
+    pub fn spawn(sample_rate: f32) -> (Self, Reader<LayerBuffers>) {
+        let (tx, rx) = mpsc::channel::<Vec<String>>();
+        let (mut writer, reader) = triple_buffer(LayerBuffers::default());
+
+        thread::spawn(move || {
+            let mut cache: HashMap<String, Arc<SampleBuffer>> = HashMap::new();
+            let mut published = LayerBuffers::default();
+            loop {
+                let mut paths = match rx.recv_timeout(PRUNE_INTERVAL) {
+                    Ok(paths) => paths,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        cache.retain(|_, buf| Arc::strong_count(buf) > 1);
+                        continue;
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                };
+                // Only the newest list matters
+                while let Ok(newer) = rx.try_recv() {
+                    paths = newer;
+                }
+
+                let mut buffers = LayerBuffers::default();
+                for (slot, path) in buffers.iter_mut().zip(&paths) {
+                    if path.is_empty() {
+                        continue;
+                    }
+                    if let Some(buf) = cache.get(path) {
+                        *slot = Some(buf.clone());
+                        continue;
+                    }
+                    match SampleBuffer::load(Path::new(path), sample_rate) {
+                        Ok(buf) => {
+                            let buf = Arc::new(buf);
+                            cache.insert(path.clone(), buf.clone());
+                            *slot = Some(buf);
+                        }
+                        Err(err) => eprintln!("warning: unable to load layer {path}: {err:#}"),
+                    }
+                }
+
+                let changed = buffers.iter().zip(&published).any(|(a, b)| match (a, b) {
+                    (Some(a), Some(b)) => !Arc::ptr_eq(a, b),
+                    (a, b) => a.is_some() != b.is_some(),
+                });
+                if changed {
+                    writer.publish(&buffers);
+                    published = buffers;
+                }
+            }
+        });
+
+        (Self { tx }, reader)
+    }
+
+    /// Queue the layer files to load; empty paths leave their slot silent
+    pub fn load(&self, paths: Vec<String>) {
+        let _ = self.tx.send(paths);
+    }
+}
+
+/// Decode every layer of `cfg` right away, for offline rendering
+pub fn load_layers(cfg: &Config, sample_rate: f32) -> Result<LayerBuffers> {
+    let mut buffers = LayerBuffers::default();
+    for (slot, layer) in buffers.iter_mut().zip(&cfg.layers) {
+        if !layer.path.is_empty() {
+            let buf = SampleBuffer::load(Path::new(&layer.path), sample_rate)
+                .with_context(|| format!("unable to load layer {}", layer.path))?;
+            *slot = Some(Arc::new(buf));
+        }
+    }
+    Ok(buffers)
+}
+
+// -------------------------
+// Playback
+// -------------------------
+
+struct Player {
+    buffer: Option<Arc<SampleBuffer>>,
+    // File changes wait until the level has faded out, then start from the top
+    next_buffer: Option<Arc<SampleBuffer>>,
+    pos: usize,
+    level: FadeSwap,
+}
+
+impl Player {
+    fn swap_pending(&self) -> bool {
+        match (&self.buffer, &self.next_buffer) {
+            (Some(a), Some(b)) => !Arc::ptr_eq(a, b),
+            (a, b) => a.is_some() != b.is_some(),
+        }
+    }
+}
+
+/// Plays the loaded loops. Swapping buffers only moves `Arc`s, so nothing here
+/// allocates or frees on the audio thread.
+pub struct LayerMixer {
+    players: [Player; MAX_LAYERS],
+}
+
+fn layer_level(cfg: &Config, slot: usize) -> f32 {
+    match cfg.layers.get(slot) {
+        Some(layer) if layer.enabled => layer.gain,
+        _ => 0.0,
+    }
+}
+
+impl LayerMixer {
+    pub fn new(cfg: &Config, sample_rate: f32) -> Self {
+        Self {
+            players: std::array::from_fn(|i| Player {
+                buffer: None,
+                next_buffer: None,
+                pos: 0,
+                level: FadeSwap::new(layer_level(cfg, i), sample_rate, LEVEL_RAMP_SECS),
+            }),
+        }
+    }
+
+    pub fn set_config(&mut self, cfg: &Config) {
+        for (i, player) in self.players.iter_mut().enumerate() {
+            let pending = player.swap_pending();
+            player.level.set_target(layer_level(cfg, i), pending);
+        }
+    }
+
+    pub fn set_buffers(&mut self, buffers: &LayerBuffers) {
+        for (player, buffer) in self.players.iter_mut().zip(buffers) {
+            player.next_buffer.clone_from(buffer);
+            let pending = player.swap_pending();
+            player.level.set_pending(pending);
+        }
+    }
+
+    /// Next left/right sample pair
+    #[inline]
+    pub fn next(&mut self) -> (f32, f32) {
+        let (mut left, mut right) = (0.0, 0.0);
+        for player in &mut self.players {
+            let (level, swap) = player.level.next(player.swap_pending());
+            if swap {
+                player.buffer.clone_from(&player.next_buffer);
+                player.pos = 0;
+            }
+            let Some(buffer) = &player.buffer else {
+                continue;
+            };
+            if level == 0.0 {
+                continue;
+            }
+            let [l, r] = buffer.frames[player.pos];
+            player.pos += 1;
+            if player.pos == buffer.frames.len() {
+                player.pos = 0;
+            }
+            left += l * level;
+            right += r * level;
+        }
+        (left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    /// Amplitude of the `freq` component of one channel (whole cycles assumed)
+    fn amplitude(frames: &[[f32; 2]], rate: f64, freq: f64) -> f64 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, frame) in frames.iter().enumerate() {
+            let phase = TAU * freq * n as f64 / rate;
+            re += frame[0] as f64 * phase.cos();
+            im -= frame[0] as f64 * phase.sin();
+        }
+        2.0 * (re * re + im * im).sqrt() / frames.len() as f64
+    }
+
+    #[test]
+    fn downsampling_keeps_ultrasonics_from_folding_back() {
+        // One second at 96 kHz: 1 kHz to keep, 30 kHz that would alias to 18 kHz at 48 kHz
+        let frames: Vec<[f32; 2]> = (0..96000)
+            .map(|n| {
+                let t = n as f64 / 96000.0;
+                let s = 0.5 * (TAU * 1000.0 * t).sin() + 0.5 * (TAU * 30000.0 * t).sin();
+                [s as f32; 2]
+            })
+            .collect();
+        let out = resample(frames, 96000.0, 48000.0);
+        assert_eq!(out.len(), 48000);
+
+        let kept = amplitude(&out, 48000.0, 1000.0);
+        let folded = amplitude(&out, 48000.0, 18000.0);
+        assert!((kept - 0.5).abs() < 0.01, "1 kHz came out at {kept}");
+        assert!(folded < 0.5 * 0.01, "30 kHz folded to 18 kHz at {folded}");
+    }
+}
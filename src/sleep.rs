@@ -0,0 +1,117 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use crate::config::Config;
+
+// Published while the timer is off (or already fired)
+const NOT_RUNNING: u64 = u64::MAX;
+// Restart slot value when no restart is pending
+const NO_RESTART: u64 = u64::MAX;
+
+fn minutes_to_frames(minutes: f32, sample_rate: f32) -> u64 {
+    (minutes * 60.0 * sample_rate) as u64
+}
+
+// -------------------------
+// Sleep Controller (UI side)
+// -------------------------
+#[derive(Clone)]
+pub struct SleepController {
+    restart: Arc<AtomicU64>,
+    remaining: Arc<AtomicU64>,
+    sample_rate: f32,
+}
+
+impl SleepController {
+    /// Start a `minutes` countdown (applied on the next buffer). The length travels with
+    /// the request, so it doesn't matter whether the audio thread has the new config yet.
+    pub fn restart(&self, minutes: f32) {
+        self.restart.store(
+            minutes_to_frames(minutes, self.sample_rate),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Whole seconds left, or `None` when the timer isn't counting down
+    pub fn remaining_secs(&self) -> Option<u64> {
+        match self.remaining.load(Ordering::Relaxed) {
+            NOT_RUNNING => None,
+            frames => Some((frames as f32 / self.sample_rate).ceil() as u64),
+        }
+    }
+}
+
+// -------------------------
+// Sleep Timer (audio side)
+// -------------------------
+pub struct SleepTimer {
+    restart: Arc<AtomicU64>,
+    published: Arc<AtomicU64>,
+    sample_rate: f32,
+    remaining: u64,
+    running: bool,
+}
+
+impl SleepTimer {
+    /// Audio-side countdown, starting at `minutes`, plus the controller the UI uses
+    /// to restart and display it
+    pub fn new(sample_rate: f32, minutes: f32) -> (Self, SleepController) {
+        let restart = Arc::new(AtomicU64::new(minutes_to_frames(minutes, sample_rate)));
+        let published = Arc::new(AtomicU64::new(NOT_RUNNING));
+        let timer = Self {
+            restart: restart.clone(),
+            published: published.clone(),
+            sample_rate,
+            remaining: 0,
+            running: false,
+        };
+        let ctrl = SleepController {
+            restart,
+            remaining: published,
+            sample_rate,
+        };
+        (timer, ctrl)
+    }
+
+    /// Scale interleaved `buffer` by the fade gain. Returns true once the timer has run out.
+    pub fn apply(&mut self, cfg: &Config, buffer: &mut [f32], channels: usize) -> bool {
+        let requested = self.restart.swap(NO_RESTART, Ordering::Relaxed);
+        if requested != NO_RESTART {
+            self.remaining = requested;
+            self.running = true;
+        }
+        if !cfg.sleep_timer || !self.running {
+            self.published.store(NOT_RUNNING, Ordering::Relaxed);
+            return false;
+        }
+
+        let fade = minutes_to_frames(cfg.sleep_fade_minutes, self.sample_rate);
+        for frame in buffer.chunks_mut(channels) {
+            if self.remaining < fade {
+                let gain = cfg
+                    .sleep_fade_curve
+                    .gain(self.remaining as f32 / fade as f32);
+                for s in frame.iter_mut() {
+                    *s *= gain;
+                }
+            }
+            if self.remaining == 0 {
+                for s in frame.iter_mut() {
+                    *s = 0.0;
+                }
+            } else {
+                self.remaining -= 1;
+            }
+        }
+
+        if self.remaining == 0 {
+            self.running = false;
+            self.published.store(NOT_RUNNING, Ordering::Relaxed);
+            return true;
+        }
+        self.published.store(self.remaining, Ordering::Relaxed);
+        false
+    }
+}
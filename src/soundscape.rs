@@ -0,0 +1,374 @@
+use std::f32::consts::{FRAC_PI_4, PI};
+
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+use crate::biquad::Biquad;
+use crate::config::{Config, DEFAULT_SAMPLE_RATE, Soundscape};
+use crate::smooth::FadeSwap;
+
+// Level fades over this long; switching generators fades out, swaps, fades in
+const LEVEL_RAMP_SECS: f32 = 0.3;
+// "SCAP": rain, wind and surf draw their own numbers instead of replaying the noise generator's
+const SEED_SALT: u64 = 0x5343_4150;
+
+#[inline]
+fn white(rng: &mut SmallRng) -> f32 {
+    rng.random_range(-1.0..1.0)
+}
+
+/// Value that wanders between `lo` and `hi`, easing to a new random target
+/// every `min_secs`..`max_secs`
+struct Drift {
+    lo: f32,
+    hi: f32,
+    min_secs: f32,
+    max_secs: f32,
+    from: f32,
+    to: f32,
+    // Position between `from` and `to` (0.0–1.0), and its speed per second
+    phase: f32,
+    speed: f32,
+}
+
+impl Drift {
+    fn new(lo: f32, hi: f32, min_secs: f32, max_secs: f32, rng: &mut SmallRng) -> Self {
+        Self {
+            lo,
+            hi,
+            min_secs,
+            max_secs,
+            from: rng.random_range(lo..hi),
+            to: rng.random_range(lo..hi),
+            phase: 0.0,
+            speed: 1.0 / rng.random_range(min_secs..max_secs),
+        }
+    }
+
+    fn advance(&mut self, secs: f32, rng: &mut SmallRng) -> f32 {
+        self.phase += self.speed * secs;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.from = self.to;
+            self.to = rng.random_range(self.lo..self.hi);
+            self.speed = 1.0 / rng.random_range(self.min_secs..self.max_secs);
+        }
+        let ease = 0.5 - 0.5 * (self.phase * PI).cos();
+        self.from + (self.to - self.from) * ease
+    }
+}
+
+// -------------------------
+// Rain
+// -------------------------
+
+const RAIN_VOICES: usize = 12;
+const DROPS_PER_SEC: f32 = 40.0;
+const RAIN_BED: f32 = 0.12;
+const RAIN_DROPS: f32 = 1.0;
+// Droplet resonance pitch range
+const DROP_MIN_HZ: f32 = 1500.0;
+const DROP_MAX_HZ: f32 = 6000.0;
+
+/// One droplet: a burst of noise through a random band-pass, decaying fast
+struct Drop {
+    env: f32,
+    decay: f32,
+    gain_l: f32,
+    gain_r: f32,
+    filter: Biquad,
+}
+
+struct Rain {
+    highpass: [Biquad; 2],
+    lowpass: [Biquad; 2],
+    drops: [Drop; RAIN_VOICES],
+    next_drop: usize,
+    intensity: Drift,
+    // Current intensity (0.5–1.0) and the matching chance of a drop per sample
+    level: f32,
+    density: f32,
+}
+
+impl Rain {
+    fn new(sample_rate: f32, rng: &mut SmallRng) -> Self {
+        let mut highpass = [Biquad::new(), Biquad::new()];
+        let mut lowpass = [Biquad::new(), Biquad::new()];
+        for (hp, lp) in highpass.iter_mut().zip(&mut lowpass) {
+            hp.update_highpass(sample_rate, 400.0, std::f32::consts::FRAC_1_SQRT_2);
+            lp.update_lowpass(sample_rate, 7000.0, std::f32::consts::FRAC_1_SQRT_2);
+        }
+        Self {
+            highpass,
+            lowpass,
+            drops: std::array::from_fn(|_| Drop {
+                env: 0.0,
+                decay: 0.0,
+                gain_l: 0.0,
+                gain_r: 0.0,
+                filter: Biquad::new(),
+            }),
+            next_drop: 0,
+            intensity: Drift::new(0.5, 1.0, 3.0, 8.0, rng),
+            level: 0.0,
+            density: 0.0,
+        }
+    }
+
+    fn control(&mut self, sample_rate: f32, frames: usize, rng: &mut SmallRng) {
+        self.level = self.intensity.advance(frames as f32 / sample_rate, rng);
+        self.density = DROPS_PER_SEC * self.level / sample_rate;
+    }
+
+    fn trigger(&mut self, sample_rate: f32, rng: &mut SmallRng) {
+        let drop = &mut self.drops[self.next_drop];
+        self.next_drop = (self.next_drop + 1) % RAIN_VOICES;
+
+        drop.env = rng.random_range(0.2..1.0);
+        drop.decay = (-1.0 / (rng.random_range(0.004..0.02) * sample_rate)).exp();
+        let angle = (rng.random_range(-1.0..1.0) + 1.0) * FRAC_PI_4;
+        drop.gain_l = angle.cos();
+        drop.gain_r = angle.sin();
+        // Pitch range shrinks at low output rates so drops stay under Nyquist
+        let top_hz = DROP_MAX_HZ.min(0.4 * sample_rate);
+        drop.filter.update_bandpass_cpg(
+            sample_rate,
+            rng.random_range(DROP_MIN_HZ..top_hz),
+            rng.random_range(1.5..4.0),
+        );
+    }
+
+    fn next(&mut self, sample_rate: f32, rng: &mut SmallRng) -> (f32, f32) {
+        if rng.random_range(0.0..1.0) < self.density {
+            self.trigger(sample_rate, rng);
+        }
+        let bed = self.level * RAIN_BED;
+        let mut left = self.lowpass[0].process(self.highpass[0].process(white(rng))) * bed;
+        let mut right = self.lowpass[1].process(self.highpass[1].process(white(rng))) * bed;
+        for drop in &mut self.drops {
+            if drop.env < 1e-4 {
+                continue;
+            }
+            let s = drop.filter.process(white(rng) * drop.env) * RAIN_DROPS;
+            drop.env *= drop.decay;
+            left += s * drop.gain_l;
+            right += s * drop.gain_r;
+        }
+        (left, right)
+    }
+}
+
+// -------------------------
+// Wind
+// -------------------------
+
+const WIND_Q: f32 = 3.0;
+const WIND_BODY: f32 = 0.3;
+const WIND_GAIN: f32 = 1.0;
+
+/// One ear of wind: its own resonance sweep and gusts so the sides move apart
+struct WindSide {
+    resonance: Biquad,
+    body: Biquad,
+    center: Drift,
+    gust: Drift,
+    level: f32,
+}
+
+struct Wind {
+    sides: [WindSide; 2],
+}
+
+impl Wind {
+    fn new(sample_rate: f32, rng: &mut SmallRng) -> Self {
+        let mut side = || {
+            let mut body = Biquad::new();
+            body.update_lowpass(sample_rate, 250.0, std::f32::consts::FRAC_1_SQRT_2);
+            WindSide {
+                resonance: Biquad::new(),
+                body,
+                center: Drift::new(200.0, 1000.0, 2.0, 6.0, rng),
+                gust: Drift::new(0.2, 1.0, 1.5, 5.0, rng),
+                level: 0.0,
+            }
+        };
+        let mut wind = Self {
+            sides: [side(), side()],
+        };
+        wind.control(sample_rate, 0, rng);
+        wind
+    }
+
+    fn control(&mut self, sample_rate: f32, frames: usize, rng: &mut SmallRng) {
+        let secs = frames as f32 / sample_rate;
+        for side in &mut self.sides {
+            let center = side.center.advance(secs, rng);
+            side.level = side.gust.advance(secs, rng);
+            // Stronger gusts whistle a little higher
+            side.resonance.update_bandpass_cpg(
+                sample_rate,
+                center * (0.75 + 0.5 * side.level),
+                WIND_Q,
+            );
+        }
+    }
+
+    fn next(&mut self, rng: &mut SmallRng) -> (f32, f32) {
+        let mut out = [0.0; 2];
+        for (side, out) in self.sides.iter_mut().zip(&mut out) {
+            let noise = white(rng);
+            let s = side.resonance.process(noise) + side.body.process(noise) * WIND_BODY;
+            *out = s * side.level * WIND_GAIN;
+        }
+        (out[0], out[1])
+    }
+}
+
+// -------------------------
+// Surf
+// -------------------------
+
+// Share of each wave spent building up; the rest is the slower wash-out
+const SURF_RISE: f32 = 0.3;
+const SURF_DECAY: f32 = 4.0;
+// Always a little water moving between waves
+const SURF_FLOOR: f32 = 0.1;
+const SURF_GAIN: f32 = 0.6;
+
+struct Surf {
+    lowpass: [Biquad; 2],
+    // Position in the current wave (0.0–1.0), its speed per second and peak height
+    phase: f32,
+    speed: f32,
+    crest: f32,
+    level: f32,
+}
+
+impl Surf {
+    fn new(sample_rate: f32, rng: &mut SmallRng) -> Self {
+        let mut surf = Self {
+            lowpass: [Biquad::new(), Biquad::new()],
+            phase: rng.random_range(0.0..1.0),
+            speed: 1.0 / rng.random_range(7.0..13.0),
+            crest: rng.random_range(0.5..1.0),
+            level: 0.0,
+        };
+        surf.control(sample_rate, 0, rng);
+        surf
+    }
+
+    /// Asymmetric swell: a smooth rise to the crest, then an exponential wash-out
+    fn envelope(&self) -> f32 {
+        if self.phase < SURF_RISE {
+            self.crest * (0.5 - 0.5 * (PI * self.phase / SURF_RISE).cos())
+        } else {
+            self.crest * (-SURF_DECAY * (self.phase - SURF_RISE) / (1.0 - SURF_RISE)).exp()
+        }
+    }
+
+    fn control(&mut self, sample_rate: f32, frames: usize, rng: &mut SmallRng) {
+        self.phase += self.speed * frames as f32 / sample_rate;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.speed = 1.0 / rng.random_range(7.0..13.0);
+            self.crest = rng.random_range(0.5..1.0);
+        }
+        let env = self.envelope();
+        self.level = SURF_FLOOR + env;
+        // The breaking crest is brighter than the wash
+        let cutoff = 200.0 + 3000.0 * env * env;
+        for lp in &mut self.lowpass {
+            lp.update_lowpass(sample_rate, cutoff, std::f32::consts::FRAC_1_SQRT_2);
+        }
+    }
+
+    fn next(&mut self, rng: &mut SmallRng) -> (f32, f32) {
+        let gain = self.level * SURF_GAIN;
+        (
+            self.lowpass[0].process(white(rng)) * gain,
+            self.lowpass[1].process(white(rng)) * gain,
+        )
+    }
+}
+
+// -------------------------
+// Layer
+// -------------------------
+
+/// Rain, wind or surf synthesized alongside the noise. Every random choice
+/// comes from one RNG seeded from the engine seed, so output is repeatable.
+pub struct SoundscapeLayer {
+    sample_rate: f32,
+    rng: SmallRng,
+    kind: Soundscape,
+    // Generator changes wait until the level has faded out, then fade back in
+    next_kind: Soundscape,
+    level: FadeSwap,
+    // Every generator filters white noise, whose level per Hz falls with sqrt(rate)
+    rate_trim: f32,
+    rain: Rain,
+    wind: Wind,
+    surf: Surf,
+}
+
+fn soundscape_level(cfg: &Config) -> f32 {
+    match cfg.soundscape {
+        Soundscape::Off => 0.0,
+        _ => cfg.soundscape_level,
+    }
+}
+
+impl SoundscapeLayer {
+    pub fn new(cfg: &Config, sample_rate: f32, seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed ^ SEED_SALT);
+        let rain = Rain::new(sample_rate, &mut rng);
+        let wind = Wind::new(sample_rate, &mut rng);
+        let surf = Surf::new(sample_rate, &mut rng);
+        Self {
+            sample_rate,
+            rng,
+            kind: cfg.soundscape,
+            next_kind: cfg.soundscape,
+            level: FadeSwap::new(soundscape_level(cfg), sample_rate, LEVEL_RAMP_SECS),
+            rate_trim: (sample_rate / DEFAULT_SAMPLE_RATE as f32).sqrt(),
+            rain,
+            wind,
+            surf,
+        }
+    }
+
+    pub fn set_config(&mut self, cfg: &Config) {
+        self.next_kind = cfg.soundscape;
+        self.level
+            .set_target(soundscape_level(cfg), self.next_kind != self.kind);
+    }
+
+    /// Block-rate updates for the active generator
+    pub fn control(&mut self, frames: usize) {
+        let (sr, rng) = (self.sample_rate, &mut self.rng);
+        match self.kind {
+            Soundscape::Off => {}
+            Soundscape::Rain => self.rain.control(sr, frames, rng),
+            Soundscape::Wind => self.wind.control(sr, frames, rng),
+            Soundscape::Surf => self.surf.control(sr, frames, rng),
+        }
+    }
+
+    /// Next left/right sample pair
+    #[inline]
+    pub fn next(&mut self) -> (f32, f32) {
+        let (level, swap) = self.level.next(self.kind != self.next_kind);
+        if swap {
+            self.kind = self.next_kind;
+        } else if level == 0.0 {
+            return (0.0, 0.0);
+        }
+        let (left, right) = match self.kind {
+            Soundscape::Off => (0.0, 0.0),
+            Soundscape::Rain => self.rain.next(self.sample_rate, &mut self.rng),
+            Soundscape::Wind => self.wind.next(&mut self.rng),
+            Soundscape::Surf => self.surf.next(&mut self.rng),
+        };
+        let gain = level * self.rate_trim;
+        (left * gain, right * gain)
+    }
+}
@@ -0,0 +1,100 @@
+use std::f32::consts::TAU;
+
+use crate::config::{Config, ToneEnvelope, ToneMode};
+use crate::smooth::{FadeSwap, Smoothed};
+
+// Level fades over this long when toggled; pitch glides a little slower
+const LEVEL_RAMP_SECS: f32 = 0.05;
+const PITCH_RAMP_SECS: f32 = 0.1;
+// Rise/fall of a square isochronic pulse, just long enough not to click
+const SQUARE_EDGE_SECS: f32 = 0.003;
+
+/// Tone layer mixed under the noise. Binaural puts a slightly different sine in
+/// each ear, `beat` Hz apart and centered on `carrier`; monaural mixes both
+/// sines into both ears; isochronic gates a single `carrier` sine `beat` times
+/// a second.
+pub struct ToneLayer {
+    sample_rate: f32,
+    mode: ToneMode,
+    // Mode changes wait until the level has faded out, then fade back in
+    next_mode: ToneMode,
+    envelope: ToneEnvelope,
+    // Phases in cycles (0.0–1.0)
+    phase_l: f32,
+    phase_r: f32,
+    pulse: f32,
+    carrier: Smoothed,
+    beat: Smoothed,
+    duty: Smoothed,
+    level: FadeSwap,
+}
+
+fn tone_level(cfg: &Config) -> f32 {
+    if cfg.tone { cfg.tone_level } else { 0.0 }
+}
+
+impl ToneLayer {
+    pub fn new(cfg: &Config, sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            mode: cfg.tone_mode,
+            next_mode: cfg.tone_mode,
+            envelope: cfg.tone_envelope,
+            phase_l: 0.0,
+            phase_r: 0.0,
+            pulse: 0.0,
+            carrier: Smoothed::new(cfg.tone_carrier, sample_rate, PITCH_RAMP_SECS),
+            beat: Smoothed::new(cfg.tone_beat, sample_rate, PITCH_RAMP_SECS),
+            duty: Smoothed::new(cfg.tone_duty, sample_rate, LEVEL_RAMP_SECS),
+            level: FadeSwap::new(tone_level(cfg), sample_rate, LEVEL_RAMP_SECS),
+        }
+    }
+
+    pub fn set_config(&mut self, cfg: &Config) {
+        self.next_mode = cfg.tone_mode;
+        self.envelope = cfg.tone_envelope;
+        self.carrier.set_target(cfg.tone_carrier);
+        self.beat.set_target(cfg.tone_beat);
+        self.duty.set_target(cfg.tone_duty);
+        self.level
+            .set_target(tone_level(cfg), self.next_mode != self.mode);
+    }
+
+    /// Next left/right sample pair
+    #[inline]
+    pub fn next(&mut self) -> (f32, f32) {
+        let (level, swap) = self.level.next(self.mode != self.next_mode);
+        if swap {
+            self.mode = self.next_mode;
+        } else if level == 0.0 {
+            return (0.0, 0.0);
+        }
+        let carrier = self.carrier.next();
+        let beat = self.beat.next();
+        let duty = self.duty.next();
+
+        let left = (self.phase_l * TAU).sin();
+        let right = (self.phase_r * TAU).sin();
+        let (out_l, out_r, freq_l, freq_r) = match self.mode {
+            ToneMode::Binaural => (left, right, carrier - 0.5 * beat, carrier + 0.5 * beat),
+            ToneMode::Monaural => {
+                let mixed = 0.5 * (left + right);
+                (mixed, mixed, carrier - 0.5 * beat, carrier + 0.5 * beat)
+            }
+            ToneMode::Isochronic => {
+                let gate = if self.pulse < duty {
+                    let edge = SQUARE_EDGE_SECS * beat / duty;
+                    self.envelope.gain(self.pulse / duty, edge)
+                } else {
+                    0.0
+                };
+                (left * gate, left * gate, carrier, carrier)
+            }
+        };
+
+        self.phase_l = (self.phase_l + freq_l / self.sample_rate).fract();
+        self.phase_r = (self.phase_r + freq_r / self.sample_rate).fract();
+        self.pulse = (self.pulse + beat / self.sample_rate).fract();
+        (out_l * level, out_r * level)
+    }
+}
@@ -0,0 +1,143 @@
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Set on the back index when it holds a value the reader hasn't seen yet
+const DIRTY: u8 = 0b100;
+const INDEX: u8 = 0b011;
+
+/// Single-producer/single-consumer triple buffer. The writer and reader each
+/// own one slot; the third ("back") slot is swapped atomically, so neither
+/// side ever waits on the other and the reader never allocates or frees.
+struct Shared<T> {
+    slots: [UnsafeCell<T>; 3],
+    back: AtomicU8,
+}
+
+// Each slot is only ever accessed by whichever side currently owns its index.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+pub struct Writer<T> {
+    shared: Arc<Shared<T>>,
+    index: u8,
+}
+
+pub struct Reader<T> {
+    shared: Arc<Shared<T>>,
+    index: u8,
+}
+
+pub fn triple_buffer<T: Clone>(initial: T) -> (Writer<T>, Reader<T>) {
+    let shared = Arc::new(Shared {
+        slots: [
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial),
+        ],
+        back: AtomicU8::new(1),
+    });
+    (
+        Writer {
+            shared: shared.clone(),
+            index: 0,
+        },
+        Reader { shared, index: 2 },
+    )
+}
+
+impl<T: Clone> Writer<T> {
+    /// Copy `value` into the write slot and make it the latest snapshot
+    pub fn publish(&mut self, value: &T) {
+        // SAFETY: the writer exclusively owns `self.index`
+        let slot = unsafe { &mut *self.shared.slots[self.index as usize].get() };
+        slot.clone_from(value);
+        let prev = self.shared.back.swap(self.index | DIRTY, Ordering::AcqRel);
+        self.index = prev & INDEX;
+    }
+}
+
+impl<T> Reader<T> {
+    /// The newest snapshot if one arrived since the last call
+    pub fn update(&mut self) -> Option<&T> {
+        if self.shared.back.load(Ordering::Relaxed) & DIRTY == 0 {
+            return None;
+        }
+        let prev = self.shared.back.swap(self.index, Ordering::AcqRel);
+        self.index = prev & INDEX;
+        Some(self.current())
+    }
+
+    /// The snapshot returned by the last `update`
+    pub fn current(&self) -> &T {
+        // SAFETY: the reader exclusively owns `self.index`
+        unsafe { &*self.shared.slots[self.index as usize].get() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writer, reader and back slot must always hold three different slots
+    fn assert_distinct<T>(writer: &Writer<T>, reader: &Reader<T>) {
+        let back = writer.shared.back.load(Ordering::Relaxed) & INDEX;
+        let mut seen = [writer.index, reader.index, back];
+        seen.sort();
+        assert_eq!(seen, [0, 1, 2]);
+    }
+
+    #[test]
+    fn back_to_back_publishes_deliver_only_the_latest() {
+        let (mut writer, mut reader) = triple_buffer(0);
+        assert_eq!(reader.update(), None);
+        assert_distinct(&writer, &reader);
+
+        for v in 1..=3 {
+            writer.publish(&v);
+            assert_distinct(&writer, &reader);
+            assert_ne!(writer.shared.back.load(Ordering::Relaxed) & DIRTY, 0);
+        }
+        assert_eq!(reader.update(), Some(&3));
+        assert_distinct(&writer, &reader);
+        assert_eq!(writer.shared.back.load(Ordering::Relaxed) & DIRTY, 0);
+
+        // Nothing new: no update, and the last snapshot stays readable
+        assert_eq!(reader.update(), None);
+        assert_eq!(*reader.current(), 3);
+
+        writer.publish(&4);
+        writer.publish(&5);
+        assert_eq!(reader.update(), Some(&5));
+        assert_eq!(reader.update(), None);
+        assert_distinct(&writer, &reader);
+    }
+
+    #[test]
+    fn concurrent_publishes_arrive_whole_and_in_order() {
+        const PUBLISHES: u64 = 200_000;
+        // Large enough that a torn copy would show up as mixed values
+        let (mut writer, mut reader) = triple_buffer([0u64; 256]);
+
+        let producer = std::thread::spawn(move || {
+            for v in 1..=PUBLISHES {
+                writer.publish(&[v; 256]);
+            }
+        });
+
+        let mut last = 0;
+        while last < PUBLISHES {
+            if let Some(snapshot) = reader.update().copied() {
+                assert!(snapshot.iter().all(|&v| v == snapshot[0]), "torn snapshot");
+                assert!(
+                    snapshot[0] > last,
+                    "went back from {last} to {}",
+                    snapshot[0]
+                );
+                last = snapshot[0];
+            }
+        }
+        producer.join().unwrap();
+
+        assert_eq!(reader.update(), None);
+    }
+}
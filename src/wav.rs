@@ -0,0 +1,181 @@
+use anyhow::{Context, Result, bail};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const BITS_PER_SAMPLE: u16 = 16;
+// fmt (24) + data header (8) + "WAVE" (4)
+const RIFF_OVERHEAD: u64 = 36;
+// RF64 adds a 36-byte ds64 chunk (header + body)
+const DS64_CHUNK: u64 = 36;
+
+/// Streaming 16-bit PCM WAV writer. The total length must be known up front;
+/// files whose data would exceed the 4 GiB RIFF limit are written as RF64.
+pub struct WavWriter {
+    out: BufWriter<File>,
+    remaining: u64,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, sample_rate: u32, channels: u16, frames: u64) -> Result<Self> {
+        let block_align = channels as u64 * (BITS_PER_SAMPLE as u64 / 8);
+        let data_len = frames * block_align;
+        let mut out = BufWriter::new(File::create(path)?);
+
+        let rf64 = data_len + RIFF_OVERHEAD > u32::MAX as u64;
+        if rf64 {
+            out.write_all(b"RF64")?;
+            out.write_all(&u32::MAX.to_le_bytes())?;
+            out.write_all(b"WAVE")?;
+            out.write_all(b"ds64")?;
+            out.write_all(&28u32.to_le_bytes())?;
+            out.write_all(&(data_len + RIFF_OVERHEAD + DS64_CHUNK).to_le_bytes())?;
+            out.write_all(&data_len.to_le_bytes())?;
+            out.write_all(&frames.to_le_bytes())?;
+            out.write_all(&0u32.to_le_bytes())?; // no extra table entries
+        } else {
+            out.write_all(b"RIFF")?;
+            out.write_all(&((data_len + RIFF_OVERHEAD) as u32).to_le_bytes())?;
+            out.write_all(b"WAVE")?;
+        }
+
+        out.write_all(b"fmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?; // PCM
+        out.write_all(&channels.to_le_bytes())?;
+        out.write_all(&sample_rate.to_le_bytes())?;
+        out.write_all(&((sample_rate as u64 * block_align) as u32).to_le_bytes())?;
+        out.write_all(&(block_align as u16).to_le_bytes())?;
+        out.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+        out.write_all(b"data")?;
+        let data_field = if rf64 { u32::MAX } else { data_len as u32 };
+        out.write_all(&data_field.to_le_bytes())?;
+
+        Ok(Self {
+            out,
+            remaining: data_len,
+        })
+    }
+
+    /// Append interleaved samples in -1.0..=1.0
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        let bytes = samples.len() as u64 * 2;
+        if bytes > self.remaining {
+            bail!("more samples written than declared in the WAV header");
+        }
+        for s in samples {
+            let v = (s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            self.out.write_all(&v.to_le_bytes())?;
+        }
+        self.remaining -= bytes;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        if self.remaining != 0 {
+            bail!("WAV closed with {} bytes still missing", self.remaining);
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+// -------------------------
+// Reading
+// -------------------------
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// Decoded audio: interleaved samples in -1.0..=1.0
+pub struct Pcm {
+    pub samples: Vec<f32>,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+/// Read a RIFF/RF64 WAV file: 8/16/24/32-bit integer PCM or 32/64-bit float
+pub fn read_wav(path: &Path) -> Result<Pcm> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < 12 || !matches!(&bytes[..4], b"RIFF" | b"RF64") || &bytes[8..12] != b"WAVE" {
+        bail!("not a WAV file");
+    }
+
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+
+    let mut format = None;
+    let mut data = None;
+    let mut rf64_data_len = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let mut len = u32_at(pos + 4) as u64;
+        let body = pos + 8;
+        match id {
+            b"ds64" if body + 16 <= bytes.len() => {
+                rf64_data_len = Some(u64::from_le_bytes(
+                    bytes[body + 8..body + 16].try_into().unwrap(),
+                ));
+            }
+            b"fmt " if body + 16 <= bytes.len() => {
+                let mut tag = u16_at(body);
+                if tag == FORMAT_EXTENSIBLE && len >= 40 && body + 26 <= bytes.len() {
+                    // Sub-format GUID starts with the plain format tag
+                    tag = u16_at(body + 24);
+                }
+                format = Some((tag, u16_at(body + 2), u32_at(body + 4), u16_at(body + 14)));
+            }
+            b"data" => {
+                if len == u32::MAX as u64 {
+                    len = rf64_data_len.unwrap_or(len);
+                }
+                let end = (body as u64 + len).min(bytes.len() as u64) as usize;
+                data = Some(body..end);
+                break;
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        pos = body + len as usize + (len as usize & 1);
+    }
+
+    let (tag, channels, sample_rate, bits) = format.context("WAV file has no fmt chunk")?;
+    let data = &bytes[data.context("WAV file has no data chunk")?];
+    if channels == 0 || sample_rate == 0 {
+        bail!("WAV file has no channels or no sample rate");
+    }
+
+    let samples: Vec<f32> = match (tag, bits) {
+        (FORMAT_PCM, 8) => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+        (FORMAT_PCM, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (FORMAT_PCM, 24) => data
+            .chunks_exact(3)
+            .map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (FORMAT_PCM, 32) => data
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes(b.try_into().unwrap()) as f32 / 2_147_483_648.0)
+            .collect(),
+        (FORMAT_FLOAT, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect(),
+        (FORMAT_FLOAT, 64) => data
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()) as f32)
+            .collect(),
+        _ => bail!("unsupported WAV encoding (format {tag}, {bits}-bit)"),
+    };
+
+    Ok(Pcm {
+        samples,
+        channels,
+        sample_rate,
+    })
+}